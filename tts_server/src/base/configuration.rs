@@ -1,14 +1,95 @@
 use super::super::error::AppError;
+use super::super::tts::engine::splitter::Pauses;
+use super::super::tts::engine::voice::VoiceConfig;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use super::pool::Priority;
 use super::trace::*;
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct AppConfigItem {
     pub log_path: Option<String>,
     pub ip: String,
     pub port: u16,
+    /// 合成线程数，默认为 CPU 核数
+    #[serde(default)]
+    pub workers: Option<usize>,
+    /// 等待合成的请求上限，超出时返回 503，默认为线程数的 4 倍
+    #[serde(default)]
+    pub queue_size: Option<usize>,
+    /// 返回 503 时 Retry-After 的秒数，默认 1
+    #[serde(default)]
+    pub retry_after: Option<u64>,
+    /// 交互式与批量请求同时排队时分给批量请求的比例，取值 (0, 1]，默认 0.2
+    #[serde(default)]
+    pub bulk_share: Option<f32>,
+    /// 每个 TFLite 解释器的推理线程数，默认 1
+    #[serde(default)]
+    pub interpreter_threads: Option<i32>,
+    /// 单次请求中同时合成的分句数，默认为 CPU 核数除以合成线程数
+    #[serde(default)]
+    pub clause_parallelism: Option<usize>,
+    /// 分句长度上限（汉字数 + 英文词数），超过时按词或韵律边界拆开，默认 40，0 表示不拆
+    #[serde(default)]
+    pub max_clause_len: Option<usize>,
+    /// 请求未指定 sil_time 时逗号、句子与段落之后的停顿（秒），默认 0.2、0.4、0.8
+    #[serde(default)]
+    pub pauses: Option<Pauses>,
+    /// API key 与优先级的对应关系，请求通过 X-Api-Key 请求头携带
+    #[serde(default)]
+    pub api_keys: Option<HashMap<String, Priority>>,
+    /// 音色列表，未配置 voices 与 voices_dir 时使用内置的标贝中文与 LJSpeech 英文
+    #[serde(default)]
+    pub voices: Option<Vec<VoiceConfig>>,
+    /// 音色中模型与 mapper 相对路径的根目录，默认为工作目录
+    #[serde(default)]
+    pub model_dir: Option<PathBuf>,
+    /// 音色包（.zip）所在目录，其中的音色追加在 voices 之后
+    #[serde(default)]
+    pub voices_dir: Option<PathBuf>,
+    /// 管理接口的密钥，请求通过 X-Admin-Key 请求头携带；未配置时禁用管理接口
+    #[serde(default)]
+    pub admin_key: Option<String>,
+}
+
+impl AppConfigItem {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        // 为 0 时交互式请求持续排队会让批量请求一直得不到调度
+        if let Some(bulk_share) = self.bulk_share.filter(|share| !(*share > 0.0 && *share <= 1.0)) {
+            anyhow::bail!("bulk_share must be within (0, 1], got {}", bulk_share);
+        }
+        Ok(())
+    }
+}
+
+// 启动时会打印配置，API key 只输出对应的优先级，管理密钥只输出是否配置
+impl std::fmt::Debug for AppConfigItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let api_keys = self
+            .api_keys
+            .as_ref()
+            .map(|keys| keys.values().map(|priority| ("<redacted>", priority)).collect::<Vec<_>>());
+        f.debug_struct("AppConfigItem")
+            .field("log_path", &self.log_path)
+            .field("ip", &self.ip)
+            .field("port", &self.port)
+            .field("workers", &self.workers)
+            .field("queue_size", &self.queue_size)
+            .field("retry_after", &self.retry_after)
+            .field("bulk_share", &self.bulk_share)
+            .field("interpreter_threads", &self.interpreter_threads)
+            .field("clause_parallelism", &self.clause_parallelism)
+            .field("max_clause_len", &self.max_clause_len)
+            .field("pauses", &self.pauses)
+            .field("api_keys", &api_keys)
+            .field("voices", &self.voices)
+            .field("model_dir", &self.model_dir)
+            .field("voices_dir", &self.voices_dir)
+            .field("admin_key", &self.admin_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -38,6 +119,7 @@ pub fn decode_config(config_file: &PathBuf) -> anyhow::Result<AppConfigItem> {
             get_default_log_path()?
         };
 
+        config_info.validate()?;
         let mut new_config_info = config_info.clone();
         new_config_info.log_path = Some(log_path.display().to_string());
         anyhow::Ok(new_config_info)
@@ -59,3 +141,32 @@ fn decode_config_data(config_file: &PathBuf) -> anyhow::Result<Option<AppConfigI
     }
     anyhow::Ok(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let config: AppConfigItem = serde_yaml::from_str(
+            "ip: 0.0.0.0\nport: 8080\nlog_path: logs\napi_keys: { key-secret-1: bulk }\nadmin_key: admin-secret-2\n",
+        )
+        .unwrap();
+        let debug = format!("{:#?}", config);
+        assert!(!debug.contains("key-secret-1"), "{}", debug);
+        assert!(!debug.contains("admin-secret-2"), "{}", debug);
+        assert!(debug.contains("Bulk"), "{}", debug);
+    }
+
+    #[test]
+    fn test_validate_bulk_share() {
+        let config = |bulk_share: &str| -> AppConfigItem {
+            serde_yaml::from_str(&format!("ip: 0.0.0.0\nport: 8080\nlog_path: logs\nbulk_share: {}\n", bulk_share)).unwrap()
+        };
+        assert!(config("0.2").validate().is_ok());
+        assert!(config("1.0").validate().is_ok());
+        assert!(config("0.0").validate().is_err());
+        assert!(config("-0.5").validate().is_err());
+        assert!(config("1.5").validate().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use super::super::tts::api::tts_handler::TTSRequest;
+use super::super::tts::engine::audio::AudioFormat;
+use super::super::tts::engine::quality::DecodeWarning;
+use super::pool::Priority;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+// 异步任务的状态文件与音频都保存在该目录下，与 query.json 相邻
+pub const JOBS_DIR: &str = "./records/jobs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+// 持久化的任务记录，每个任务一个 <id>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    pub status: JobStatus,
+    pub request: TTSRequest,
+    pub format: AudioFormat,
+    pub callback_url: Option<String>,
+    /// 调度优先级，旧版本保存的任务按批量处理
+    #[serde(default = "bulk")]
+    pub priority: Priority,
+    /// 分句总数，开始合成前为 0
+    pub clauses_total: usize,
+    pub clauses_done: usize,
+    pub sample_rate: Option<usize>,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+    /// 重试后仍未通过解码检查的片段，随分句合成逐步追加
+    #[serde(default)]
+    pub warnings: Vec<DecodeWarning>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn bulk() -> Priority {
+    Priority::Bulk
+}
+
+impl JobRecord {
+    pub fn audio_file(&self) -> String {
+        format!("{}.{}", self.id, self.format.extension())
+    }
+}
+
+// 管理异步任务：内存索引 + 磁盘状态，并把待执行的任务 id 交给后台线程
+pub struct JobStore {
+    dir: PathBuf,
+    jobs: Mutex<HashMap<String, JobRecord>>,
+    queue: Mutex<mpsc::Sender<String>>,
+}
+
+impl JobStore {
+    // 加载目录下已有的任务，未完成的任务（含重启前正在执行的）按创建时间重新排队
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<(JobStore, mpsc::Receiver<String>)> {
+        let dir = dir.as_ref().to_path_buf();
+        create_dir_all(&dir)?;
+
+        let mut jobs = HashMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match fs::read(&path).map(|data| serde_json::from_slice::<JobRecord>(&data)) {
+                    Ok(Ok(job)) => {
+                        jobs.insert(job.id.clone(), job);
+                    }
+                    _ => tracing::warn!("skip unreadable job file: {}", path.display()),
+                }
+            }
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let mut pending: Vec<&mut JobRecord> = jobs.values_mut().filter(|job| !job.status.is_finished()).collect();
+        pending.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        for job in pending {
+            job.status = JobStatus::Queued;
+            job.clauses_done = 0;
+            job.warnings.clear();
+            let _ = tx.send(job.id.clone());
+        }
+
+        let store = JobStore {
+            dir,
+            jobs: Mutex::new(jobs),
+            queue: Mutex::new(tx),
+        };
+        Ok((store, rx))
+    }
+
+    pub fn audio_path(&self, job: &JobRecord) -> PathBuf {
+        self.dir.join(job.audio_file())
+    }
+
+    fn save(&self, job: &JobRecord) -> io::Result<()> {
+        // 先写临时文件再改名，避免进程中断时留下不完整的状态文件
+        let path = self.dir.join(format!("{}.json", job.id));
+        let tmp = self.dir.join(format!("{}.json.tmp", job.id));
+        fs::write(&tmp, serde_json::to_vec(job)?)?;
+        fs::rename(tmp, path)
+    }
+
+    // 保存新任务并排队
+    pub fn submit(&self, job: JobRecord) -> io::Result<()> {
+        self.save(&job)?;
+        let id = job.id.clone();
+        self.jobs.lock().unwrap().insert(id.clone(), job);
+        let _ = self.queue.lock().unwrap().send(id);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobRecord> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    // 修改任务并落盘，返回修改后的记录
+    pub fn update<F: FnOnce(&mut JobRecord)>(&self, id: &str, f: F) -> Option<JobRecord> {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.get_mut(id)?;
+            f(job);
+            job.clone()
+        };
+        if let Err(e) = self.save(&job) {
+            tracing::warn!("failed to save job {}: {}", id, e);
+        }
+        Some(job)
+    }
+
+    // 删除已结束的任务及其音频
+    pub fn remove(&self, id: &str) -> Option<JobRecord> {
+        let job = self.jobs.lock().unwrap().remove(id)?;
+        let _ = fs::remove_file(self.audio_path(&job));
+        let _ = fs::remove_file(self.dir.join(format!("{}.json", job.id)));
+        Some(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::tts::engine::quality::DecodeIssue;
+
+    fn job(id: &str, status: JobStatus, created_at: &str) -> JobRecord {
+        JobRecord {
+            id: id.to_string(),
+            status,
+            request: serde_json::from_str(r#"{"text": "你好"}"#).unwrap(),
+            format: AudioFormat::Wav,
+            callback_url: None,
+            priority: Priority::Bulk,
+            clauses_total: 3,
+            clauses_done: 1,
+            sample_rate: None,
+            duration_ms: None,
+            error: None,
+            warnings: Vec::new(),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+        }
+    }
+
+    fn warning() -> DecodeWarning {
+        DecodeWarning { clause: 1, text: "你好".to_string(), issue: DecodeIssue::TooLong }
+    }
+
+    #[test]
+    fn test_job_store_resume() {
+        let dir = std::env::temp_dir().join(format!("tts_jobs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        {
+            let (store, rx) = JobStore::open(&dir).unwrap();
+            store.submit(job("b", JobStatus::Queued, "2025-01-01 00:00:02")).unwrap();
+            store.submit(job("a", JobStatus::Running, "2025-01-01 00:00:01")).unwrap();
+            store.submit(job("c", JobStatus::Succeeded, "2025-01-01 00:00:00")).unwrap();
+            assert_eq!(rx.try_iter().count(), 3);
+            store.update("b", |job| job.clauses_done = 2);
+            store.update("c", |job| job.warnings.push(warning()));
+            store.update("a", |job| job.warnings.push(warning()));
+        }
+
+        // 重启后未完成的任务按创建时间重新排队，进度清零
+        let (store, rx) = JobStore::open(&dir).unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        let b = store.get("b").unwrap();
+        assert_eq!((b.status, b.clauses_done), (JobStatus::Queued, 0));
+        assert!(store.get("a").unwrap().warnings.is_empty());
+        let c = store.get("c").unwrap();
+        assert_eq!((c.status, c.warnings), (JobStatus::Succeeded, vec![warning()]));
+        assert!(store.remove("c").is_some());
+        assert!(store.get("c").is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
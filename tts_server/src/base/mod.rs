@@ -1,3 +1,5 @@
 pub mod configuration;
 pub mod trace;
-pub mod record;
\ No newline at end of file
+pub mod record;
+pub mod job;
+pub mod pool;
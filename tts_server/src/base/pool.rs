@@ -0,0 +1,384 @@
+use super::super::error::AppError;
+use prettytable::{format, Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{self, warn};
+
+// 默认排队上限为线程数的倍数
+const DEFAULT_QUEUE_PER_WORKER: usize = 4;
+// 拒绝请求时建议客户端等待的秒数
+pub const DEFAULT_RETRY_AFTER: u64 = 1;
+// 两类请求同时排队时，分给批量请求的调度比例
+pub const DEFAULT_BULK_SHARE: f32 = 0.2;
+
+// 请求的优先级：交互式请求优先调度，批量请求按比例保留一部分线程
+// 按调度先后排序，Interactive < Bulk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    Interactive,
+    Bulk,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 2] = [Priority::Interactive, Priority::Bulk];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn parse(value: &str) -> Option<Priority> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "interactive" => Some(Priority::Interactive),
+            "bulk" => Some(Priority::Bulk),
+            _ => None,
+        }
+    }
+}
+
+// 单个优先级的调度统计
+#[derive(Debug, Clone, Default)]
+pub struct ClassStats {
+    /// 正在排队的任务数
+    pub queued: usize,
+    /// 已开始执行的任务数
+    pub started: usize,
+    /// 因队列已满被拒绝的任务数
+    pub rejected: usize,
+    /// 已开始执行的任务的排队时长之和
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl ClassStats {
+    pub fn avg_wait(&self) -> Duration {
+        match self.started {
+            0 => Duration::ZERO,
+            started => self.total_wait / started as u32,
+        }
+    }
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+struct QueuedTask {
+    task: Task,
+    enqueued: Instant,
+}
+
+struct Queue {
+    tasks: [VecDeque<QueuedTask>; 2],
+    stats: [ClassStats; 2],
+    // 两类都有任务排队时累计批量请求的份额，满 1 时调度一个批量任务
+    bulk_credit: f32,
+    // 正在执行任务的线程数
+    busy: usize,
+    closed: bool,
+}
+
+impl Queue {
+    fn len(&self) -> usize {
+        self.tasks.iter().map(VecDeque::len).sum()
+    }
+
+    // 空闲线程会立即取走任务，不计入排队上限
+    fn capacity(&self, workers: usize, queue_size: usize) -> usize {
+        queue_size + workers.saturating_sub(self.busy)
+    }
+
+    fn pop(&mut self, bulk_share: f32) -> Option<QueuedTask> {
+        let interactive = Priority::Interactive.index();
+        let bulk = Priority::Bulk.index();
+        let priority = match (self.tasks[interactive].is_empty(), self.tasks[bulk].is_empty()) {
+            (true, true) => return None,
+            (false, true) => interactive,
+            (true, false) => bulk,
+            (false, false) => {
+                self.bulk_credit += bulk_share;
+                if self.bulk_credit >= 1.0 {
+                    self.bulk_credit -= 1.0;
+                    bulk
+                } else {
+                    interactive
+                }
+            }
+        };
+        let queued = self.tasks[priority].pop_front()?;
+        let wait = queued.enqueued.elapsed();
+        let stats = &mut self.stats[priority];
+        stats.queued -= 1;
+        stats.started += 1;
+        stats.total_wait += wait;
+        stats.max_wait = stats.max_wait.max(wait);
+        Some(queued)
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    // 有新任务或线程池关闭
+    available: Condvar,
+    // 队列有空位
+    space: Condvar,
+}
+
+// 合成线程池：固定数量的工作线程 + 按优先级分类的有界队列，队列已满时立即拒绝，不在异步执行器上阻塞
+pub struct WorkerPool {
+    shared: Arc<Shared>,
+    workers: usize,
+    queue_size: usize,
+    retry_after: u64,
+}
+
+impl WorkerPool {
+    // workers、queue_size 为 None 时分别使用 CPU 核数与其 4 倍
+    pub fn new(workers: Option<usize>, queue_size: Option<usize>, retry_after: Option<u64>, bulk_share: Option<f32>) -> Self {
+        let workers = workers
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+            .max(1);
+        let queue_size = queue_size.unwrap_or(workers * DEFAULT_QUEUE_PER_WORKER);
+        let bulk_share = bulk_share.unwrap_or(DEFAULT_BULK_SHARE).clamp(0.0, 1.0);
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                tasks: Default::default(),
+                stats: Default::default(),
+                bulk_credit: 0.0,
+                busy: 0,
+                closed: false,
+            }),
+            available: Condvar::new(),
+            space: Condvar::new(),
+        });
+        for i in 0..workers {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name(format!("tts-worker-{}", i))
+                .spawn(move || worker_loop(&shared, bulk_share))
+                .expect("failed to spawn synthesis worker");
+        }
+        Self {
+            shared,
+            workers,
+            queue_size,
+            retry_after: retry_after.unwrap_or(DEFAULT_RETRY_AFTER),
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    // 提交任务，不等待结果；所有线程忙且队列已满时返回 Overloaded
+    pub fn spawn<F>(&self, priority: Priority, task: F) -> Result<(), AppError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.push(priority, Box::new(task), false)
+    }
+
+    // 同 spawn，队列已满时阻塞等待空位，只用于后台线程
+    pub fn spawn_blocking<F>(&self, priority: Priority, task: F) -> Result<(), AppError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.push(priority, Box::new(task), true)
+    }
+
+    fn push(&self, priority: Priority, task: Task, wait: bool) -> Result<(), AppError> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        while queue.len() >= queue.capacity(self.workers, self.queue_size) && !queue.closed {
+            if !wait {
+                queue.stats[priority.index()].rejected += 1;
+                return Err(AppError::Overloaded(self.retry_after));
+            }
+            queue = self.shared.space.wait(queue).unwrap();
+        }
+        if queue.closed {
+            return Err(AppError::Internal("synthesis workers stopped".to_string()));
+        }
+        queue.tasks[priority.index()].push_back(QueuedTask { task, enqueued: Instant::now() });
+        queue.stats[priority.index()].queued += 1;
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    // 提交任务，返回接收结果的通道；异步环境中 await，阻塞线程中 blocking_recv
+    pub fn submit<F, T>(&self, priority: Priority, task: F) -> Result<oneshot::Receiver<T>, AppError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.spawn(priority, move || {
+            let _ = tx.send(task());
+        })?;
+        Ok(rx)
+    }
+
+    // 提交任务并等待结果
+    pub async fn run<F, T>(&self, priority: Priority, task: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.submit(priority, task)?.await.map_err(|_| task_aborted())
+    }
+
+    // 在后台线程中提交任务并等待结果，队列已满时等待而不是拒绝
+    pub fn run_blocking<F, T>(&self, priority: Priority, task: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.spawn_blocking(priority, move || {
+            let _ = tx.send(task());
+        })?;
+        rx.blocking_recv().map_err(|_| task_aborted())
+    }
+
+    // 各优先级的调度统计，顺序同 Priority::ALL
+    pub fn stats(&self) -> Vec<(Priority, ClassStats)> {
+        let queue = self.shared.queue.lock().unwrap();
+        Priority::ALL.iter().map(|&priority| (priority, queue.stats[priority.index()].clone())).collect()
+    }
+
+    pub fn to_table_string(&self) -> String {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.add_row(Row::new(vec![
+            Cell::new("Priority"),
+            Cell::new("Queued"),
+            Cell::new("Started"),
+            Cell::new("Rejected"),
+            Cell::new("Avg Wait (s)"),
+            Cell::new("Max Wait (s)"),
+        ]));
+        for (priority, stats) in self.stats() {
+            table.add_row(Row::new(vec![
+                Cell::new(&format!("{:?}", priority).to_lowercase()),
+                Cell::new(&stats.queued.to_string()),
+                Cell::new(&stats.started.to_string()),
+                Cell::new(&stats.rejected.to_string()),
+                Cell::new(&(stats.avg_wait().as_millis() as f64 / 1000.0).to_string()),
+                Cell::new(&(stats.max_wait.as_millis() as f64 / 1000.0).to_string()),
+            ]));
+        }
+        format!("Synthesis Workers: {}, Queue Size: {}\n{}", self.workers, self.queue_size, table)
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().closed = true;
+        self.shared.available.notify_all();
+        self.shared.space.notify_all();
+    }
+}
+
+fn worker_loop(shared: &Shared, bulk_share: f32) {
+    loop {
+        // 取到任务后立即释放锁，再执行任务
+        let task = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if let Some(task) = queue.pop(bulk_share) {
+                    queue.busy += 1;
+                    break task;
+                }
+                if queue.closed {
+                    return;
+                }
+                queue = shared.available.wait(queue).unwrap();
+            }
+        };
+        if panic::catch_unwind(AssertUnwindSafe(task.task)).is_err() {
+            warn!("synthesis task panicked");
+        }
+        shared.queue.lock().unwrap().busy -= 1;
+        shared.space.notify_one();
+    }
+}
+
+// 任务在返回结果前 panic
+pub fn task_aborted() -> AppError {
+    AppError::Internal("synthesis task aborted".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    // 占住唯一的线程，返回放行用的发送端
+    fn block_worker(pool: &WorkerPool) -> mpsc::Sender<()> {
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        pool.spawn(Priority::Interactive, move || {
+            started_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        })
+        .unwrap();
+        started_rx.recv().unwrap();
+        release_tx
+    }
+
+    #[test]
+    fn test_worker_pool() {
+        let pool = WorkerPool::new(Some(1), Some(1), Some(3), None);
+        assert_eq!(pool.submit(Priority::Interactive, || 1 + 1).unwrap().blocking_recv(), Ok(2));
+
+        // 唯一的线程被占用、队列中已有一个任务时，新任务被拒绝
+        let release = block_worker(&pool);
+        let queued = pool.submit(Priority::Bulk, || "queued").unwrap();
+        assert!(matches!(pool.submit(Priority::Interactive, || "rejected"), Err(AppError::Overloaded(3))));
+
+        release.send(()).unwrap();
+        assert_eq!(queued.blocking_recv(), Ok("queued"));
+
+        // panic 的任务不会让线程退出
+        assert!(pool.submit(Priority::Interactive, || -> i32 { panic!("boom") }).unwrap().blocking_recv().is_err());
+        assert_eq!(pool.submit(Priority::Interactive, || 3).unwrap().blocking_recv(), Ok(3));
+
+        let stats = pool.stats();
+        assert_eq!((stats[0].1.started, stats[0].1.rejected), (4, 1));
+        assert_eq!((stats[1].1.started, stats[1].1.rejected), (1, 0));
+    }
+
+    #[test]
+    fn test_priority_order() {
+        for (bulk_share, expected) in [
+            (0.0, vec!["i1", "i2", "i3", "b1", "b2"]),
+            (0.5, vec!["i1", "b1", "i2", "b2", "i3"]),
+        ] {
+            let pool = WorkerPool::new(Some(1), Some(8), None, Some(bulk_share));
+            let order = Arc::new(Mutex::new(Vec::new()));
+            let release = block_worker(&pool);
+            let mut results = Vec::new();
+            for (priority, name) in [
+                (Priority::Bulk, "b1"),
+                (Priority::Bulk, "b2"),
+                (Priority::Interactive, "i1"),
+                (Priority::Interactive, "i2"),
+                (Priority::Interactive, "i3"),
+            ] {
+                let order = order.clone();
+                results.push(pool.submit(priority, move || order.lock().unwrap().push(name)).unwrap());
+            }
+            release.send(()).unwrap();
+            for result in results {
+                result.blocking_recv().unwrap();
+            }
+            assert_eq!(*order.lock().unwrap(), expected);
+        }
+    }
+}
@@ -1,24 +1,114 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{http::{header, StatusCode}, HttpResponse, ResponseError};
 use thiserror::Error;
+use super::tts::engine::error::TTSError;
 
-#[derive(Error, Debug, Clone, Copy)]
+#[derive(Error, Debug, Clone)]
 pub enum AppError {
     #[error("convert.yaml does not exist")]
     ConfigFileLost,
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("none of the accepted media types is supported: {0}")]
+    NotAcceptable(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("internal error: {0}")]
+    Internal(String),
+    #[error("server is busy, retry after {0}s")]
+    Overloaded(u64),
+    #[error(transparent)]
+    Synthesis(#[from] TTSError),
 }
 
+// 错误码对外保持稳定，只能新增不能修改
 pub fn to_integer(data: &AppError) -> u32 {
-    *data as u32
+    match data {
+        AppError::ConfigFileLost => 0,
+        AppError::InvalidParameter(_) => 1,
+        AppError::NotAcceptable(_) => 2,
+        AppError::NotFound(_) => 3,
+        AppError::Conflict(_) => 4,
+        AppError::Internal(_) => 5,
+        AppError::Overloaded(_) => 6,
+        AppError::Forbidden(_) => 7,
+        // 合成错误：1xx 文本前端，2xx 模型，3xx 音频处理
+        AppError::Synthesis(e) => match e {
+            TTSError::EmptyText => 100,
+            TTSError::UnknownSymbol { .. } => 101,
+            TTSError::Frontend(_) => 102,
+            TTSError::ModelLoad(_) => 200,
+            TTSError::Inference(_) => 201,
+            TTSError::Audio(_) => 300,
+        },
+    }
+}
+
+// 错误响应体
+#[derive(serde::Serialize, utoipa::ToSchema, Debug)]
+pub struct ErrorResponse {
+    /// 稳定的错误码，见 to_integer
+    pub code: u32,
+    /// 错误描述
+    pub message: String,
+}
+
+impl From<&AppError> for ErrorResponse {
+    fn from(e: &AppError) -> Self {
+        Self {
+            code: to_integer(e),
+            message: e.to_string(),
+        }
+    }
 }
 
 impl ResponseError for AppError {
     fn status_code(&self) -> StatusCode {
-        match *self {
+        match self {
+            AppError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Overloaded(_) => StatusCode::SERVICE_UNAVAILABLE,
+            // 输入文本无法合成属于请求错误，模型与音频处理失败属于服务端错误
+            AppError::Synthesis(e) if e.is_input_error() => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Synthesis(_) => StatusCode::INTERNAL_SERVER_ERROR,
             _ => StatusCode::BAD_REQUEST,
         }
     }
-    //错误标准返回，business_code 为内部错误码
+    //错误标准返回，code 为内部错误码
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).body(self.to_string())
+        let mut response = HttpResponse::build(self.status_code());
+        if let AppError::Overloaded(retry_after) = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+        }
+        response.json(ErrorResponse::from(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_response() {
+        let cases = [
+            (AppError::InvalidParameter("text".to_string()), StatusCode::BAD_REQUEST, 1),
+            (AppError::Synthesis(TTSError::EmptyText), StatusCode::UNPROCESSABLE_ENTITY, 100),
+            (AppError::Synthesis(TTSError::Inference("invoke".to_string())), StatusCode::INTERNAL_SERVER_ERROR, 201),
+            (AppError::Synthesis(TTSError::Audio("resample".to_string())), StatusCode::INTERNAL_SERVER_ERROR, 300),
+            (AppError::Overloaded(2), StatusCode::SERVICE_UNAVAILABLE, 6),
+            (AppError::Forbidden("admin".to_string()), StatusCode::FORBIDDEN, 7),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code(), status);
+            assert_eq!(ErrorResponse::from(&error).code, code);
+        }
+        let response = AppError::Overloaded(2).error_response();
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "2");
     }
 }
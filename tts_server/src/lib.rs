@@ -4,10 +4,31 @@ pub mod tts;
 
 use tts::engine::tts_engine::TTSEngine;
 use base::record::QueryTracker;
+use base::job::JobStore;
+use base::pool::{Priority, WorkerPool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tts::reload::Reloader;
 
-// 定义全局状态
+// 定义全局状态；引擎只读共享，查询记录单独加锁，不与合成互相阻塞
 pub struct AppState {
-    pub engine: TTSEngine,
-    pub track: QueryTracker,
+    pub engine: RwLock<Arc<TTSEngine>>,
+    pub track: Mutex<QueryTracker>,
+    pub jobs: Arc<JobStore>,
+    pub pool: WorkerPool,
+    pub api_keys: HashMap<String, Priority>,
+    pub reloader: Reloader,
+}
+
+impl AppState {
+    // 当前的引擎；请求开始时取一次，重新加载后进行中的请求继续使用旧引擎直到结束
+    pub fn engine(&self) -> Arc<TTSEngine> {
+        self.engine.read().unwrap().clone()
+    }
+
+    // 替换引擎，新请求立即使用新引擎
+    pub fn swap_engine(&self, engine: TTSEngine) {
+        *self.engine.write().unwrap() = Arc::new(engine);
+    }
 }
 
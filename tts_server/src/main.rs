@@ -22,7 +22,7 @@ pub fn main()-> anyhow::Result<()> {
 
     info!("tts_server start with config: {:#?}", config_data);
 
-    if let Some(err) = tts_server::tts::server::start(&config_data).err() {
+    if let Some(err) = tts_server::tts::server::start(&args.config, &config_data).err() {
         error!("error: {}", err);
     }
 
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use super::super::super::AppState;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::reload::ReloadResponse;
+
+// 管理接口的密钥请求头
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/reload",
+    params(("X-Admin-Key" = String, Header, description = "Admin key configured as admin_key")),
+    responses(
+        (status = 200, description = "Voices reloaded and swapped in for new requests", body = ReloadResponse),
+        (status = 403, description = "Admin endpoints are disabled or the key is wrong", body = ErrorResponse),
+        (status = 409, description = "Another reload is in progress", body = ErrorResponse),
+        (status = 500, description = "Loading or test synthesis failed, the old voices keep serving", body = ErrorResponse)
+    ),
+    tag = "Admin API"
+)]
+#[actix_web::post("/api/admin/reload")]
+pub async fn api_admin_reload(data: web::Data<AppState>, req: HttpRequest) -> Result<HttpResponse, AppError> {
+    let key = req.headers().get(ADMIN_KEY_HEADER).and_then(|value| value.to_str().ok());
+    data.reloader.authorize(key)?;
+
+    // 加载模型与试合成耗时较长，在阻塞线程中执行
+    let state = data.clone();
+    let response = web::block(move || state.reloader.reload(&state))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+    Ok(HttpResponse::Ok().json(response))
+}
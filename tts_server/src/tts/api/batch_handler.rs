@@ -0,0 +1,271 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use tracing::{self, info, warn};
+use std::collections::HashSet;
+use futures::StreamExt;
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use super::super::super::AppState;
+use super::super::super::base::pool::{task_aborted, Priority};
+use super::super::super::error::{self, AppError, ErrorResponse};
+use super::super::engine::audio::{self, AudioFormat};
+use super::super::engine::quality::DecodeWarning;
+use super::super::engine::tts_engine::TTSEngine;
+use super::tts_handler::{deny_unknown_fields, resolve_priority, TTSRequest, UnknownFields};
+use chrono::Local;
+use zip::write::SimpleFileOptions;
+
+// 单次批量请求的条目上限
+pub const MAX_BATCH_ITEMS: usize = 500;
+// 单次批量请求所有条目的总字符数上限，批量接口同步返回整个压缩包，更大的工作量应使用 /api/jobs
+pub const MAX_BATCH_CHARS: usize = 100_000;
+// 批量请求体的大小上限：文本按 UTF-8 每字符最多 4 字节，另为每个条目的其余字段留 1KB，
+// 保证不超过上述上限的批次不会先被请求体大小限制拒绝
+pub const MAX_BATCH_BODY_BYTES: usize = MAX_BATCH_CHARS * 4 + MAX_BATCH_ITEMS * 1024;
+const MAX_ID_CHARS: usize = 128;
+
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct BatchItem {
+    /// 条目标识，用作压缩包内的文件名，只能包含字母、数字、'-'、'_' 和 '.'
+    #[schema(example = "welcome")]
+    pub id: String,
+    #[serde(flatten)]
+    pub request: TTSRequest,
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BatchRequest {
+    /// 要合成的条目，最多 500 条，文本合计最多 100000 字；请求体最大约 0.9MB
+    pub items: Vec<BatchItem>,
+    /// 条目未指定 format 时使用的输出格式，默认 wav
+    pub format: Option<AudioFormat>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema, Debug)]
+pub struct BatchItemReport {
+    pub id: String,
+    /// 压缩包内的文件名，合成失败时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 音频时长（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// 失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 失败时的错误码，与接口错误响应的 code 一致
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u32>,
+    /// 重试后仍未通过解码检查的片段
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DecodeWarning>,
+}
+
+// 压缩包内的 report.json
+#[derive(serde::Serialize, utoipa::ToSchema, Debug)]
+pub struct BatchReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemReport>,
+}
+
+fn validate_id(id: &str) -> Result<(), AppError> {
+    let valid = !id.is_empty()
+        && id.chars().count() <= MAX_ID_CHARS
+        && !id.starts_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidParameter(format!("invalid item id: {:?}", id)))
+    }
+}
+
+impl BatchRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.items.is_empty() {
+            return Err(AppError::InvalidParameter("items must not be empty".to_string()));
+        }
+        if self.items.len() > MAX_BATCH_ITEMS {
+            return Err(AppError::InvalidParameter(format!(
+                "batch has {} items, the limit is {}",
+                self.items.len(),
+                MAX_BATCH_ITEMS
+            )));
+        }
+        let chars: usize = self.items.iter().map(|item| item.request.text.chars().count()).sum();
+        if chars > MAX_BATCH_CHARS {
+            return Err(AppError::InvalidParameter(format!(
+                "batch has {} characters in total, the limit is {}; submit larger work to /api/jobs",
+                chars, MAX_BATCH_CHARS
+            )));
+        }
+        let mut ids = HashSet::new();
+        for item in &self.items {
+            validate_id(&item.id)?;
+            deny_unknown_fields(&item.unknown)?;
+            if !ids.insert(item.id.as_str()) {
+                return Err(AppError::InvalidParameter(format!("duplicate item id: {}", item.id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 单个条目的合成结果
+struct ItemAudio {
+    file: String,
+    data: Vec<u8>,
+    duration_ms: u64,
+    warnings: Vec<DecodeWarning>,
+}
+
+// 合成单个条目；参数错误或合成失败只影响该条目
+fn synthesize_item(engine: &TTSEngine, item: &BatchItem, format: Option<AudioFormat>) -> Result<ItemAudio, AppError> {
+    let format = item.request.format.or(format).unwrap_or_default();
+    let params = item.request.to_params(format, engine)?;
+    let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+
+    let synthesis = engine.synthesis_with_report(&item.request.text, &params)?;
+    let duration_ms = (synthesis.audio.len() as u64 * 1000) / sample_rate as u64;
+    Ok(ItemAudio {
+        file: format!("{}.{}", item.id, format.extension()),
+        data: audio::encode(&synthesis.audio, sample_rate, format)?,
+        duration_ms,
+        warnings: synthesis.warnings,
+    })
+}
+
+fn build_zip(files: Vec<(String, Vec<u8>)>, report: &BatchReport) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    // 音频数据基本不可再压缩，直接存储
+    let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, data) in files {
+        writer.start_file(name, stored)?;
+        writer.write_all(&data)?;
+    }
+    writer.start_file("report.json", SimpleFileOptions::default())?;
+    writer.write_all(&serde_json::to_vec_pretty(report).unwrap_or_default())?;
+    Ok(writer.finish()?.into_inner())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tts/batch",
+    request_body = BatchRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, bulk by default")),
+    responses(
+        (status = 200, description = "Zip archive with one audio file per successful item, named <id>.<ext>, plus report.json (BatchReport) listing every item", content_type = "application/zip"),
+        (status = 400, description = "Bad request, including more than 500 items or more than 100000 characters in total (use /api/jobs for larger work); items with invalid parameters or text over 10000 characters fail individually in report.json", body = ErrorResponse),
+        (status = 413, description = "Request body larger than 100000 × 4 + 500 × 1024 bytes (about 0.9 MB)"),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+// 需要单独的请求体大小上限，在 server.rs 中按资源注册
+pub async fn api_tts_batch(data: web::Data<AppState>, req: HttpRequest, body: web::Json<BatchRequest>) -> Result<HttpResponse, AppError> {
+    let batch = body.into_inner();
+    batch.validate()?;
+    let priority = resolve_priority(&req, &data, Priority::Bulk)?;
+
+    // 每个条目作为单独的任务按批次的优先级提交到合成线程池，同时最多占用 workers 个位置，
+    // 与其他请求一样受排队上限和 bulk_share 约束；第一个条目在队列已满时返回 503，其余条目等待队列空位
+    let start_time = Local::now();
+    let engine = data.engine();
+    let format = batch.format;
+    let items = Arc::new(batch.items);
+    let mut first = Some(data.pool.submit(priority, {
+        let (engine, items) = (engine.clone(), items.clone());
+        move || synthesize_item(&engine, &items[0], format)
+    })?);
+    let results: Vec<Result<ItemAudio, AppError>> = futures::stream::iter(0..items.len())
+        .map(|i| {
+            let first = first.take();
+            let (state, engine, items) = (data.clone(), engine.clone(), items.clone());
+            async move {
+                let result = match first {
+                    Some(receiver) => receiver.await.map_err(|_| task_aborted()),
+                    None => web::block(move || {
+                        state.pool.run_blocking(priority, move || synthesize_item(&engine, &items[i], format))
+                    })
+                    .await
+                    .map_err(|e| AppError::Internal(e.to_string()))
+                    .and_then(|result| result),
+                };
+                result.and_then(|result| result)
+            }
+        })
+        .buffered(data.pool.workers())
+        .collect()
+        .await;
+
+    let mut files = Vec::new();
+    let mut reports = Vec::new();
+    for (item, result) in items.iter().zip(results) {
+        match result {
+            Ok(audio) => {
+                reports.push(BatchItemReport {
+                    id: item.id.clone(),
+                    file: Some(audio.file.clone()),
+                    duration_ms: Some(audio.duration_ms),
+                    error: None,
+                    code: None,
+                    warnings: audio.warnings,
+                });
+                files.push((audio.file, audio.data));
+            }
+            Err(error) => {
+                warn!("batch item {} failed: {}", item.id, error);
+                reports.push(BatchItemReport {
+                    id: item.id.clone(),
+                    file: None,
+                    duration_ms: None,
+                    error: Some(error.to_string()),
+                    code: Some(error::to_integer(&error)),
+                    warnings: Vec::new(),
+                });
+            }
+        }
+    }
+    let report = BatchReport {
+        succeeded: files.len(),
+        failed: reports.len() - files.len(),
+        items: reports,
+    };
+
+    let duration = Local::now().signed_duration_since(start_time);
+    {
+        let mut track = data.track.lock().unwrap();
+        for item in items.iter() {
+            track.record_query(
+                item.request.text.clone(),
+                start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                std::time::Duration::from_millis(duration.num_milliseconds() as u64 / items.len() as u64),
+            );
+        }
+    }
+    info!(
+        "batch req: {} items, {} failed, cost: {:.2}s",
+        items.len(),
+        report.failed,
+        duration.num_milliseconds() as f64 / 1000.0
+    );
+
+    let result = web::block(move || build_zip(files, &report))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    match result {
+        Ok(archive) => Ok(HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"batch.zip\""))
+            .body(archive)),
+        Err(e) => {
+            warn!("failed to build batch archive: {}", e);
+            Err(AppError::Internal(format!("failed to build batch archive: {}", e)))
+        }
+    }
+}
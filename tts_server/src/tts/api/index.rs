@@ -1,6 +1,4 @@
 use actix_web::{web, HttpRequest, HttpResponse};
-use std::sync::Arc;
-use tokio::sync::RwLock;
 use super::super::super::AppState;
 
 #[utoipa::path(
@@ -11,11 +9,12 @@ use super::super::super::AppState;
     )
 )]
 #[actix_web::get("/")]
-pub async fn index(data: web::Data<Arc<RwLock<AppState>>>, _req: HttpRequest) -> HttpResponse {
-    let track_string = {
-        let app_state = data.read().await;
-        app_state.track.to_table_string() // Clone track output while holding lock
-    };
+pub async fn index(data: web::Data<AppState>, _req: HttpRequest) -> HttpResponse {
+    let track_string = format!(
+        "{}\n\n{}",
+        data.track.lock().unwrap().to_table_string(),
+        data.pool.to_table_string()
+    );
 
     HttpResponse::Ok()
     .content_type("text/plain; charset=utf-8")
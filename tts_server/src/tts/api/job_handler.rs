@@ -0,0 +1,417 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use tracing::{self, info, warn};
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use super::super::super::AppState;
+use super::super::super::base::job::{JobRecord, JobStatus, JobStore};
+use super::super::super::base::pool::Priority;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::engine::audio::{self, AudioFormat};
+use super::super::engine::quality::DecodeWarning;
+use super::tts_handler::{deny_unknown_fields, resolve_priority, TTSRequest, UnknownFields};
+use chrono::Local;
+use lazy_static::lazy_static;
+
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    // 回调连接（含重定向）都经过 resolve_public，避免 DNS 重绑定到内网地址
+    static ref CALLBACK_AGENT: ureq::Agent = ureq::AgentBuilder::new()
+        .timeout(CALLBACK_TIMEOUT)
+        .resolver(resolve_public)
+        .build();
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct JobRequest {
+    #[serde(flatten)]
+    pub request: TTSRequest,
+    /// 任务结束（成功、失败或取消）时以 POST 方式回调的地址，请求体为 JobResponse；
+    /// 只允许解析到公网地址的 http(s) 地址
+    #[schema(example = "https://example.com/tts/callback")]
+    pub callback_url: Option<String>,
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema, Debug)]
+pub struct JobResponse {
+    pub id: String,
+    pub status: JobStatus,
+    /// 分句总数，开始合成前为 0
+    pub clauses_total: usize,
+    /// 已合成的分句数
+    pub clauses_done: usize,
+    pub format: AudioFormat,
+    pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 任务成功后音频的下载地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_url: Option<String>,
+    /// 重试后仍未通过解码检查的片段
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DecodeWarning>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<&JobRecord> for JobResponse {
+    fn from(job: &JobRecord) -> Self {
+        Self {
+            id: job.id.clone(),
+            status: job.status,
+            clauses_total: job.clauses_total,
+            clauses_done: job.clauses_done,
+            format: job.format,
+            priority: job.priority,
+            sample_rate: job.sample_rate,
+            duration_ms: job.duration_ms,
+            error: job.error.clone(),
+            audio_url: (job.status == JobStatus::Succeeded).then(|| format!("/api/jobs/{}/audio", job.id)),
+            warnings: job.warnings.clone(),
+            created_at: job.created_at.clone(),
+            updated_at: job.updated_at.clone(),
+        }
+    }
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/jobs",
+    request_body = JobRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, bulk by default")),
+    responses(
+        (status = 202, description = "Job accepted", body = JobResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::post("/api/jobs")]
+pub async fn api_job_submit(data: web::Data<AppState>, req: HttpRequest, body: web::Json<JobRequest>) -> Result<HttpResponse, AppError> {
+    let JobRequest { request, callback_url, unknown } = body.into_inner();
+    deny_unknown_fields(&unknown)?;
+    if let Some(url) = callback_url.clone() {
+        web::block(move || check_callback_url(&url))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+    }
+    let format = request.format.unwrap_or_default();
+    let priority = resolve_priority(&req, &data, Priority::Bulk)?;
+    request.to_params(format, &data.engine())?;
+    let jobs = data.jobs.clone();
+
+    let created_at = now();
+    let job = JobRecord {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        status: JobStatus::Queued,
+        request,
+        format,
+        callback_url,
+        priority,
+        clauses_total: 0,
+        clauses_done: 0,
+        sample_rate: None,
+        duration_ms: None,
+        error: None,
+        warnings: Vec::new(),
+        created_at: created_at.clone(),
+        updated_at: created_at,
+    };
+    let response = JobResponse::from(&job);
+    if let Err(e) = jobs.submit(job) {
+        warn!("failed to save job {}: {}", response.id, e);
+        return Err(AppError::Internal(format!("failed to save job {}", response.id)));
+    }
+    info!("job {} submitted", response.id);
+    Ok(HttpResponse::Accepted().json(response))
+}
+
+fn find_job(data: &web::Data<AppState>, id: &str) -> Result<(Arc<JobStore>, JobRecord), AppError> {
+    let jobs = data.jobs.clone();
+    let job = jobs.get(id).ok_or_else(|| AppError::NotFound(format!("job {}", id)))?;
+    Ok((jobs, job))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job status and progress in clauses", body = JobResponse),
+        (status = 404, description = "Job not found", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::get("/api/jobs/{id}")]
+pub async fn api_job_status(data: web::Data<AppState>, id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    let (_, job) = find_job(&data, &id)?;
+    Ok(HttpResponse::Ok().json(JobResponse::from(&job)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}/audio",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Synthesized audio in the format requested at submission", content_type = "audio/wav"),
+        (status = 404, description = "Job not found", body = ErrorResponse),
+        (status = 409, description = "Job has not succeeded", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::get("/api/jobs/{id}/audio")]
+pub async fn api_job_audio(data: web::Data<AppState>, id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    let (jobs, job) = find_job(&data, &id)?;
+    if job.status != JobStatus::Succeeded {
+        return Err(AppError::Conflict(format!("job {} is {:?}", job.id, job.status)));
+    }
+    match tokio::fs::read(jobs.audio_path(&job)).await {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type(job.format.content_type(job.sample_rate.unwrap_or_default()))
+            .body(body)),
+        Err(e) => {
+            warn!("failed to read audio of job {}: {}", job.id, e);
+            Err(AppError::NotFound(format!("audio of job {}", job.id)))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/jobs/{id}",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Unfinished jobs are cancelled, finished jobs are deleted together with their audio", body = JobResponse),
+        (status = 404, description = "Job not found", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::delete("/api/jobs/{id}")]
+pub async fn api_job_delete(data: web::Data<AppState>, id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    let (jobs, job) = find_job(&data, &id)?;
+    let job = if job.status.is_finished() {
+        jobs.remove(&job.id)
+    } else {
+        // 正在执行的任务在下一个分句完成后停止
+        jobs.update(&job.id, |job| {
+            job.status = JobStatus::Cancelled;
+            job.updated_at = now();
+        })
+    };
+    let job = job.ok_or_else(|| AppError::NotFound(format!("job {}", id)))?;
+    Ok(HttpResponse::Ok().json(JobResponse::from(&job)))
+}
+
+// 后台线程按提交顺序逐个执行任务
+pub fn start_worker(data: web::Data<AppState>, jobs: Arc<JobStore>, queue: mpsc::Receiver<String>) {
+    std::thread::spawn(move || {
+        for id in queue {
+            run_job(&data, &jobs, &id);
+        }
+    });
+}
+
+fn run_job(data: &web::Data<AppState>, jobs: &JobStore, id: &str) {
+    // 排队期间被取消或删除的任务直接跳过
+    let job = match jobs.get(id) {
+        Some(job) if job.status == JobStatus::Queued => job,
+        Some(job) => {
+            if let (JobStatus::Cancelled, Some(url)) = (job.status, &job.callback_url) {
+                notify(url, &JobResponse::from(&job));
+            }
+            return;
+        }
+        None => return,
+    };
+    let start_time = Local::now();
+    let job = jobs
+        .update(id, |job| {
+            job.status = JobStatus::Running;
+            job.updated_at = now();
+        })
+        .unwrap_or(job);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| synthesize_job(data, jobs, &job)))
+        .unwrap_or_else(|_| Err("synthesis failed".to_string()));
+    let job = jobs.update(id, |job| {
+        match result {
+            // 最后一个分句或编码期间被取消，状态保持 cancelled
+            _ if job.status == JobStatus::Cancelled => {}
+            Ok(Some((sample_rate, duration_ms))) => {
+                job.status = JobStatus::Succeeded;
+                job.sample_rate = Some(sample_rate);
+                job.duration_ms = Some(duration_ms);
+            }
+            // 已被取消，状态保持 cancelled
+            Ok(None) => {}
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e);
+            }
+        }
+        job.updated_at = now();
+    });
+    // 被取消的任务不保留已写入的音频
+    if let Some(job) = job.as_ref().filter(|job| job.status == JobStatus::Cancelled) {
+        let _ = std::fs::remove_file(jobs.audio_path(job));
+    }
+
+    let duration = Local::now().signed_duration_since(start_time);
+    data.track.lock().unwrap().record_query(
+        job.as_ref().map(|job| job.request.text.clone()).unwrap_or_default(),
+        start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        Duration::from_millis(duration.num_milliseconds() as u64),
+    );
+    info!("job {} finished cost: {:.2}s", id, duration.num_milliseconds() as f64 / 1000.0);
+
+    if let Some(job) = job {
+        if let Some(url) = &job.callback_url {
+            notify(url, &JobResponse::from(&job));
+        }
+    }
+}
+
+// 逐句合成；返回采样率与音频时长，被取消时返回 None
+fn synthesize_job(data: &web::Data<AppState>, jobs: &JobStore, job: &JobRecord) -> Result<Option<(usize, u64)>, String> {
+    // 整个任务使用同一个引擎，期间重新加载不影响该任务
+    let engine = data.engine();
+    let params = job
+        .request
+        .to_params(job.format, &engine)
+        .map_err(|e| e.to_string())?;
+    let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+    let clauses = engine.clauses(&job.request.text, &params).map_err(|e| e.to_string())?;
+    jobs.update(&job.id, |job| job.clauses_total = clauses.len());
+
+    let mut samples = Vec::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        // 在合成线程池中按任务的优先级调度，队列已满时等待
+        let (engine, clause, clause_params) = (engine.clone(), clause.clone(), params.clone());
+        let (chunk, warnings) = data
+            .pool
+            .run_blocking(job.priority, move || engine.synthesize_clause_chunk(i, &clause, &clause_params))
+            .and_then(|chunk| chunk.map_err(AppError::from))
+            .map_err(|e| e.to_string())?;
+        samples.extend(chunk);
+
+        let cancelled = jobs
+            .update(&job.id, |job| {
+                job.clauses_done = i + 1;
+                job.warnings.extend(warnings);
+                job.updated_at = now();
+            })
+            .is_none_or(|job| job.status == JobStatus::Cancelled);
+        if cancelled {
+            return Ok(None);
+        }
+    }
+
+    let body = audio::encode(&samples, sample_rate, job.format).map_err(|e| e.to_string())?;
+    std::fs::write(jobs.audio_path(job), body).map_err(|e| e.to_string())?;
+    Ok(Some((sample_rate, (samples.len() * 1000 / sample_rate) as u64)))
+}
+
+// 回调地址须为 http(s)，且主机只能解析到公网地址，避免借服务端访问内网（SSRF）
+fn check_callback_url(url: &str) -> Result<(), AppError> {
+    let invalid = |reason: String| AppError::InvalidParameter(format!("callback_url {}: {}", url, reason));
+    let parsed = CALLBACK_AGENT.post(url).request_url().map_err(|e| invalid(e.to_string()))?;
+    let parsed = parsed.as_url();
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid("must be an http(s) URL".to_string()));
+    }
+    let host = parsed.host_str().unwrap_or_default();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    resolve_public(&format!("{}:{}", host, port)).map_err(|e| invalid(e.to_string()))?;
+    Ok(())
+}
+
+// 解析主机地址，任一地址不是公网地址时拒绝
+fn resolve_public(netloc: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+    match addrs.iter().find(|addr| !is_public(addr.ip())) {
+        Some(addr) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} resolves to non-public address {}", netloc, addr.ip()),
+        )),
+        None => Ok(addrs),
+    }
+}
+
+// 排除回环、私有、链路本地、运营商 NAT、未指定、组播和广播等地址
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(a == 0
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || (a == 100 && (64..128).contains(&b))
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                || ip.is_multicast()),
+        },
+    }
+}
+
+fn notify(url: &str, body: &JobResponse) {
+    match CALLBACK_AGENT.post(url).send_json(body) {
+        Ok(_) => info!("job {} callback sent to {}", body.id, url),
+        Err(e) => warn!("job {} callback to {} failed: {}", body.id, url, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_callback_url() {
+        for url in [
+            "ftp://93.184.215.14/callback",
+            "not a url",
+            "http://127.0.0.1:8080/callback",
+            "http://localhost/callback",
+            "http://10.0.0.1/callback",
+            "http://172.16.5.4/callback",
+            "http://192.168.1.1/callback",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/callback",
+            "http://0.0.0.0/callback",
+            "http://[::1]/callback",
+            "http://[fd00::1]/callback",
+            "http://[fe80::1]/callback",
+            "http://[::ffff:127.0.0.1]/callback",
+        ] {
+            assert!(
+                matches!(check_callback_url(url), Err(AppError::InvalidParameter(_))),
+                "{}",
+                url
+            );
+        }
+        assert!(check_callback_url("https://93.184.215.14/callback").is_ok());
+        assert!(check_callback_url("http://[2606:2800:21f:cb07:6820:80da:af6b:8b2c]:8080/callback").is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::Engine;
+use tracing::{self, info};
+use super::super::super::AppState;
+use super::super::super::base::pool::Priority;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::engine::audio::{self, AudioFormat};
+use super::super::engine::quality::{format_warnings, DecodeWarning};
+use super::super::engine::timing::{self, MarksFormat, SpeechMark};
+use super::tts_handler::{deny_unknown_fields, resolve_format, resolve_priority, TTSRequest, UnknownFields, WARNINGS_HEADER};
+use chrono::Local;
+
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct MarksRequest {
+    #[serde(flatten)]
+    pub request: TTSRequest,
+    /// 返回格式：json 语音标记、srt 或 vtt 字幕，默认 json
+    pub marks_format: Option<MarksFormat>,
+    /// json 格式下是否同时返回 base64 编码的音频，保证音频与时间标记来自同一次合成
+    #[serde(default)]
+    pub include_audio: bool,
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct MarksResponse {
+    /// 音频采样率（Hz）
+    pub sample_rate: usize,
+    /// 音频格式
+    pub format: AudioFormat,
+    /// 音频总时长（毫秒）
+    pub duration_ms: u64,
+    /// 分句及字/词的时间标记，按开始时间排列
+    pub marks: Vec<SpeechMark>,
+    /// base64 编码的音频，仅在 include_audio 为 true 时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    /// 重试后仍未通过解码检查的片段
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DecodeWarning>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tts/marks",
+    request_body = MarksRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, interactive by default")),
+    responses(
+        (status = 200, description = "Speech marks as JSON, or subtitles as SRT (application/x-subrip) or WebVTT (text/vtt)", body = MarksResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 406, description = "None of the accepted media types is supported"),
+        (status = 422, description = "Text cannot be synthesized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::post("/api/tts/marks")]
+pub async fn api_tts_marks(data: web::Data<AppState>, req: HttpRequest, body: web::Json<MarksRequest>) -> Result<HttpResponse, AppError> {
+    let MarksRequest { request, marks_format, include_audio, unknown } = body.into_inner();
+    deny_unknown_fields(&unknown)?;
+    // 不返回音频时 Accept 请求头针对的是字幕格式，不参与音频格式协商
+    let format = match (include_audio, request.format) {
+        (true, format) => resolve_format(&req, format)?,
+        (false, format) => format.unwrap_or_default(),
+    };
+    let priority = resolve_priority(&req, &data, Priority::Interactive)?;
+    let start_time = Local::now();
+
+    let engine = data.engine();
+    let params = request.to_params(format, &engine)?;
+    let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+    let text = request.text.clone();
+    let synthesis = data.pool.run(priority, move || engine.synthesis_with_report(&text, &params)).await??;
+    let (wav, marks) = (synthesis.audio, synthesis.marks);
+
+    let duration = Local::now().signed_duration_since(start_time);
+    data.track.lock().unwrap().record_query(
+        request.text.clone(),
+        start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        std::time::Duration::from_millis(duration.num_milliseconds() as u64),
+    );
+    info!("marks req: {:?} cost: {:.2}s", request.text, duration.num_milliseconds() as f64 / 1000.0);
+
+    let mut response = HttpResponse::Ok();
+    if !synthesis.warnings.is_empty() {
+        response.insert_header((WARNINGS_HEADER, format_warnings(&synthesis.warnings)));
+    }
+    let response = match marks_format.unwrap_or_default() {
+        MarksFormat::Json => {
+            let audio = match include_audio {
+                true => Some(base64::engine::general_purpose::STANDARD.encode(audio::encode(&wav, sample_rate, format)?)),
+                false => None,
+            };
+            response.json(MarksResponse {
+                sample_rate,
+                format,
+                duration_ms: timing::to_ms(wav.len() as f32 / sample_rate as f32),
+                marks,
+                audio,
+                warnings: synthesis.warnings,
+            })
+        }
+        MarksFormat::Srt => response
+            .content_type("application/x-subrip; charset=utf-8")
+            .body(timing::to_srt(&marks)),
+        MarksFormat::Vtt => response
+            .content_type("text/vtt; charset=utf-8")
+            .body(timing::to_webvtt(&marks)),
+    };
+    Ok(response)
+}
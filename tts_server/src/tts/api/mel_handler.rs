@@ -0,0 +1,107 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::Engine;
+use tracing::{self, info};
+use super::super::super::AppState;
+use super::super::super::base::pool::Priority;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::engine::audio::AudioFormat;
+use super::super::engine::mel::{ClauseMel, MelFormat};
+use super::super::engine::tts_engine::TextType;
+use super::tts_handler::{deny_unknown_fields, resolve_priority, TTSRequest, UnknownFields};
+use chrono::Local;
+
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct MelRequest {
+    /// 与合成接口相同的参数，format、sample_rate 与停顿不影响梅尔谱
+    #[serde(flatten)]
+    pub request: TTSRequest,
+    /// 梅尔谱的编码格式，默认 npy
+    pub mel_format: Option<MelFormat>,
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub unknown: UnknownFields,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct MelClause {
+    /// 分句文本
+    pub text: String,
+    /// 分句语言
+    pub lang: TextType,
+    /// 合成所用的音色
+    pub voice: String,
+    /// 输入声学模型的音素 id
+    pub phoneme_ids: Vec<i32>,
+    /// 梅尔谱的形状：[帧数, 梅尔维数]
+    pub shape: [usize; 2],
+    /// 音色的原生采样率（Hz）
+    pub sample_rate: usize,
+    /// 每帧对应的采样数
+    pub hop_size: usize,
+    /// base64 编码的梅尔谱
+    pub data: String,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct MelResponse {
+    /// data 的编码格式
+    pub format: MelFormat,
+    /// 按顺序排列的分句，停顿与没有可朗读内容的分句不输出
+    pub clauses: Vec<MelClause>,
+}
+
+impl MelClause {
+    fn new(mel: ClauseMel, format: MelFormat) -> Self {
+        Self {
+            shape: mel.shape(),
+            data: base64::engine::general_purpose::STANDARD.encode(mel.encode(format)),
+            text: mel.text,
+            lang: mel.lang,
+            voice: mel.voice,
+            phoneme_ids: mel.input_ids,
+            sample_rate: mel.sample_rate,
+            hop_size: mel.hop_size,
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tts/mel",
+    request_body = MelRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, interactive by default")),
+    responses(
+        (status = 200, description = "Per-clause mel spectrograms from the acoustic model, without vocoding", body = MelResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 422, description = "Text cannot be synthesized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::post("/api/tts/mel")]
+pub async fn api_tts_mel(data: web::Data<AppState>, req: HttpRequest, body: web::Json<MelRequest>) -> Result<HttpResponse, AppError> {
+    let MelRequest { request, mel_format, unknown } = body.into_inner();
+    deny_unknown_fields(&unknown)?;
+    let format = mel_format.unwrap_or_default();
+    let priority = resolve_priority(&req, &data, Priority::Interactive)?;
+    let start_time = Local::now();
+
+    let engine = data.engine();
+    let params = request.to_params(AudioFormat::default(), &engine)?;
+    let text = request.text.clone();
+    let mels = data.pool.run(priority, move || engine.mel_with_params(&text, &params)).await??;
+
+    let duration = Local::now().signed_duration_since(start_time);
+    data.track.lock().unwrap().record_query(
+        request.text.clone(),
+        start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        std::time::Duration::from_millis(duration.num_milliseconds() as u64),
+    );
+    info!("mel req: {:?} cost: {:.2}s", request.text, duration.num_milliseconds() as f64 / 1000.0);
+
+    Ok(HttpResponse::Ok().json(MelResponse {
+        format,
+        clauses: mels.into_iter().map(|mel| MelClause::new(mel, format)).collect(),
+    }))
+}
@@ -1,2 +1,10 @@
 pub mod tts_handler;
+pub mod stream_handler;
+pub mod marks_handler;
+pub mod mel_handler;
+pub mod batch_handler;
+pub mod job_handler;
+pub mod ws_handler;
+pub mod voices_handler;
+pub mod admin_handler;
 pub mod index;
\ No newline at end of file
@@ -0,0 +1,82 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use bytes::Bytes;
+use tracing::{self, info, warn};
+use tokio::sync::mpsc;
+use super::super::super::AppState;
+use super::super::super::base::pool::Priority;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::engine::audio::{self, AudioFormat};
+use super::tts_handler::{resolve_format, resolve_priority, TTSRequest};
+use chrono::Local;
+
+#[utoipa::path(
+    post,
+    path = "/api/tts/stream",
+    request_body = TTSRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, interactive by default")),
+    responses(
+        (status = 200, description = "Chunked audio, one chunk per clause. wav is sent with an open-length header, pcm and G.711 are headerless, flac and ogg are not supported", content_type = "audio/wav"),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 406, description = "None of the accepted media types is supported"),
+        (status = 422, description = "Text cannot be synthesized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::post("/api/tts/stream")]
+pub async fn api_tts_stream(data: web::Data<AppState>, req: HttpRequest, body: web::Json<TTSRequest>) -> Result<HttpResponse, AppError> {
+    let format = resolve_format(&req, body.format)?;
+    if !format.is_streamable() {
+        return Err(AppError::InvalidParameter(format!(
+            "format {:?} cannot be streamed, use /api/tts instead",
+            format
+        )));
+    }
+    let priority = resolve_priority(&req, &data, Priority::Interactive)?;
+    let engine = data.engine();
+    let params = body.to_params(format, &engine)?;
+    // 在发出响应头之前发现无法分句的文本
+    engine.clauses(&body.text, &params)?;
+    let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+    let text = body.into_inner().text;
+
+    // 每个分句合成完成后立即发送，通道容量限制未被客户端取走的分句数量；
+    // 响应头发出后出错时向流中写入错误，中断响应
+    let (tx, rx) = mpsc::channel::<Result<Bytes, AppError>>(4);
+    if format == AudioFormat::Wav {
+        let _ = tx.send(Ok(Bytes::from(audio::wav_header(sample_rate, None)))).await;
+    }
+
+    // 合成在线程池中执行，队列已满时在发出响应头之前返回 503
+    let state = data.clone();
+    data.pool.spawn(priority, move || {
+        let start_time = Local::now();
+        let result = engine.synthesis_stream(&text, &params, |chunk| {
+            // 客户端断开或编码失败后停止合成
+            let bytes = audio::encode_chunk(&chunk, format).map(Bytes::from).map_err(AppError::from);
+            let ok = bytes.is_ok();
+            tx.blocking_send(bytes).is_ok() && ok
+        });
+        if let Err(e) = result {
+            warn!("stream req: {:?} failed: {}", text, e);
+            let _ = tx.blocking_send(Err(e.into()));
+            return;
+        }
+
+        let duration = Local::now().signed_duration_since(start_time);
+        state.track.lock().unwrap().record_query(
+            text.clone(),
+            start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            std::time::Duration::from_millis(duration.num_milliseconds() as u64),
+        );
+        info!("stream req: {:?} cost: {:.2}s", text, duration.num_milliseconds() as f64 / 1000.0);
+    })?;
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk.map_err(actix_web::Error::from), rx))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type(sample_rate))
+        .streaming(stream))
+}
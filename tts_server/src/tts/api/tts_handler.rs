@@ -1,62 +1,390 @@
-use actix_web::{web, HttpResponse};
-use tracing::{self, info};
-use std::io::Cursor;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use tracing::{self, info, warn};
 use super::super::super::AppState;
+use super::super::super::base::pool::Priority;
+use super::super::super::error::{AppError, ErrorResponse};
+use super::super::engine::audio::{self, AudioFormat};
+use super::super::engine::quality::format_warnings;
+use super::super::engine::ssml;
+use super::super::engine::tts_engine::{SynthesisParams, TTSEngine, TextType};
 use chrono::Local;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// 请求参数的取值范围
+pub const MAX_TEXT_CHARS: usize = 10000;
+pub const MAX_SIL_TIME: f32 = 5.0;
+pub const MIN_SAMPLE_RATE: usize = 8000;
+pub const MAX_SAMPLE_RATE: usize = 48000;
+pub const MIN_PROSODY_RATIO: f32 = 0.5;
+pub const MAX_PROSODY_RATIO: f32 = 2.0;
+
+// 以 #[serde(flatten)] 嵌入 TTSRequest 时 deny_unknown_fields 不生效，
+// 外层结构在其后再 flatten 一个该类型的字段，收集两者都未识别的字段
+pub type UnknownFields = HashMap<String, serde::de::IgnoredAny>;
 
-#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub fn deny_unknown_fields(fields: &UnknownFields) -> Result<(), AppError> {
+    if fields.is_empty() {
+        return Ok(());
+    }
+    let mut names: Vec<&str> = fields.keys().map(String::as_str).collect();
+    names.sort();
+    Err(AppError::InvalidParameter(format!("unknown field(s): {}", names.join(", "))))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
 pub struct TTSQuery {
     /// 要合成语音的文本
     #[schema(example = "今天天气怎么样？明天大概有50%的概率下雨，请记得带伞。")]
     text: String,
+    /// 输出音频格式，不填时按 Accept 请求头选择，默认 wav
+    format: Option<AudioFormat>,
+    /// 输出采样率（Hz），默认使用引擎采样率 24000，mulaw/alaw 默认 8000
+    sample_rate: Option<usize>,
+    /// 说话人名称，见 /api/voices
+    speaker: Option<String>,
+    /// 语速倍率，默认 1.0
+    speed: Option<f32>,
+    /// 音高倍率，默认 1.0，仅 FastSpeech2 音色生效
+    pitch: Option<f32>,
+    /// 能量倍率，默认 1.0，仅 FastSpeech2 音色生效
+    energy: Option<f32>,
+}
+
+impl From<TTSQuery> for TTSRequest {
+    fn from(query: TTSQuery) -> Self {
+        Self {
+            text: query.text,
+            sil_time: None,
+            lang: None,
+            sample_rate: query.sample_rate,
+            format: query.format,
+            voice: None,
+            speaker: query.speaker,
+            speed: query.speed,
+            pitch: query.pitch,
+            energy: query.energy,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TTSRequest {
+    /// 要合成语音的文本，以 <speak> 开头时按 SSML 解析
+    #[schema(example = "今天天气怎么样？明天大概有50%的概率下雨，请记得带伞。", min_length = 1, max_length = 10000)]
+    pub text: String,
+    /// 分句之间统一的停顿时长（秒），不填时按标点使用逗号、句子、段落三级停顿
+    #[schema(example = 0.2, minimum = 0.0, maximum = 5.0)]
+    pub sil_time: Option<f32>,
+    /// 强制指定语言，不填时按分句自动识别
+    pub lang: Option<TextType>,
+    /// 输出采样率（Hz），默认使用引擎采样率 24000，mulaw/alaw 默认 8000
+    #[schema(example = 24000, minimum = 8000, maximum = 48000)]
+    pub sample_rate: Option<usize>,
+    /// 输出音频格式，不填时按 Accept 请求头选择，默认 wav
+    pub format: Option<AudioFormat>,
+    /// 音色名称，如 baker、ljspeech
+    #[schema(example = "baker")]
+    pub voice: Option<String>,
+    /// 说话人名称，见 /api/voices；同时决定所用的音色
+    pub speaker: Option<String>,
+    /// 语速倍率，默认 1.0，大于 1 时更快
+    #[schema(example = 1.0, minimum = 0.5, maximum = 2.0)]
+    pub speed: Option<f32>,
+    /// 音高（基频）倍率，默认 1.0，仅 FastSpeech2 音色生效
+    #[schema(example = 1.0, minimum = 0.5, maximum = 2.0)]
+    pub pitch: Option<f32>,
+    /// 能量倍率，默认 1.0，仅 FastSpeech2 音色生效
+    #[schema(example = 1.0, minimum = 0.5, maximum = 2.0)]
+    pub energy: Option<f32>,
+}
+
+impl TTSRequest {
+    // 校验请求参数，并转换为引擎合成参数
+    pub fn to_params(&self, format: AudioFormat, engine: &TTSEngine) -> Result<SynthesisParams, AppError> {
+        self.validate_text()?;
+        self.options_to_params(format, engine)
+    }
+
+    pub fn validate_text(&self) -> Result<(), AppError> {
+        let text_chars = self.text.chars().count();
+        if self.text.trim().is_empty() {
+            return Err(AppError::InvalidParameter("text must not be empty".to_string()));
+        }
+        if text_chars > MAX_TEXT_CHARS {
+            return Err(AppError::InvalidParameter(format!(
+                "text is {} characters long, the limit is {}",
+                text_chars, MAX_TEXT_CHARS
+            )));
+        }
+        if ssml::is_ssml(&self.text) {
+            ssml::parse(&self.text).map_err(|e| AppError::InvalidParameter(format!("invalid ssml: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    // 只校验 text 以外的参数
+    pub fn options_to_params(&self, format: AudioFormat, engine: &TTSEngine) -> Result<SynthesisParams, AppError> {
+        let mut params = SynthesisParams {
+            sample_rate: format.default_sample_rate(),
+            ..Default::default()
+        };
+        if let Some(sil_time) = self.sil_time {
+            if !(0.0..=MAX_SIL_TIME).contains(&sil_time) {
+                return Err(AppError::InvalidParameter(format!(
+                    "sil_time must be within [0, {}] seconds",
+                    MAX_SIL_TIME
+                )));
+            }
+            params.sil_time = Some(sil_time);
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+                return Err(AppError::InvalidParameter(format!(
+                    "sample_rate must be within [{}, {}] Hz",
+                    MIN_SAMPLE_RATE, MAX_SAMPLE_RATE
+                )));
+            }
+            params.sample_rate = Some(sample_rate);
+        }
+        for (name, value, target) in [
+            ("speed", self.speed, &mut params.speed),
+            ("pitch", self.pitch, &mut params.pitch),
+            ("energy", self.energy, &mut params.energy),
+        ] {
+            if let Some(value) = value {
+                if !(MIN_PROSODY_RATIO..=MAX_PROSODY_RATIO).contains(&value) {
+                    return Err(AppError::InvalidParameter(format!(
+                        "{} must be within [{}, {}]",
+                        name, MIN_PROSODY_RATIO, MAX_PROSODY_RATIO
+                    )));
+                }
+                *target = value;
+            }
+        }
+        params.text_type = self.lang;
+        if let Some(name) = &self.voice {
+            let voice = engine
+                .find_voice(name)
+                .ok_or_else(|| AppError::InvalidParameter(format!("unknown voice: {}", name)))?;
+            if self.lang.is_some_and(|lang| lang != voice.lang) {
+                return Err(AppError::InvalidParameter(format!(
+                    "voice {} does not speak {:?}",
+                    name, voice.lang
+                )));
+            }
+            params.text_type = Some(voice.lang);
+            params.voice = Some(name.clone());
+        }
+        // 未指定音色时由说话人决定音色，说话人所属的音色必须与 voice、lang 一致
+        if let Some(speaker) = &self.speaker {
+            let voice = match &self.voice {
+                Some(name) => engine.find_voice(name).filter(|voice| voice.has_speaker(speaker)),
+                None => engine.speaker_voice(speaker),
+            }
+            .ok_or_else(|| AppError::InvalidParameter(format!("unknown speaker: {}", speaker)))?;
+            if params.text_type.is_some_and(|lang| lang != voice.lang) {
+                return Err(AppError::InvalidParameter(format!(
+                    "speaker {} does not speak {:?}",
+                    speaker, voice.lang
+                )));
+            }
+            params.text_type = Some(voice.lang);
+            params.voice = Some(voice.name.clone());
+            params.speaker = Some(speaker.clone());
+        }
+
+        Ok(params)
+    }
+}
+
+// 重试后仍有分句未通过解码检查时返回的响应头，值为 "分句序号:问题" 的列表
+pub const WARNINGS_HEADER: &str = "x-tts-warnings";
+
+// 指定优先级的请求头，API key 已配置优先级时忽略
+pub const PRIORITY_HEADER: &str = "x-priority";
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// 优先级：已配置的 API key 优先，其次 X-Priority 请求头，最后使用接口的默认优先级；
+// 配置了 API key 时，没有有效 key 的请求只能通过请求头降为 bulk，不能提升为 interactive
+pub fn resolve_priority(req: &HttpRequest, data: &AppState, default: Priority) -> Result<Priority, AppError> {
+    let header_value = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+    if let Some(priority) = header_value(API_KEY_HEADER).and_then(|key| data.api_keys.get(key)) {
+        return Ok(*priority);
+    }
+    let requested = match header_value(PRIORITY_HEADER) {
+        Some(value) => Priority::parse(value)
+            .ok_or_else(|| AppError::InvalidParameter(format!("unknown priority: {}", value)))?,
+        None => return Ok(default),
+    };
+    Ok(match data.api_keys.is_empty() {
+        true => requested,
+        false => requested.max(default),
+    })
+}
+
+// 输出格式：显式参数优先，其次按 Accept 请求头协商，默认 wav
+pub fn resolve_format(req: &HttpRequest, format: Option<AudioFormat>) -> Result<AudioFormat, AppError> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+    match req.headers().get(header::ACCEPT).and_then(|accept| accept.to_str().ok()) {
+        Some(accept) => AudioFormat::negotiate(accept).ok_or_else(|| AppError::NotAcceptable(accept.to_string())),
+        None => Ok(AudioFormat::default()),
+    }
 }
 
 #[utoipa::path(
     get,
     path = "/api/tts",
+    params(
+        TTSQuery,
+        ("X-Priority" = Option<Priority>, Header, description = "Scheduling class, interactive by default"),
+    ),
     responses(
-        (status = 200, description = "Successfully got tts response", content_type = "audio/wav"),
-        (status = 400, description = "Bad request"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Successfully got tts response, encoded as wav, pcm, mulaw (audio/PCMU), alaw (audio/PCMA), flac (audio/flac) or ogg (audio/ogg; codecs=vorbis)", content_type = "audio/wav",
+            headers(("X-TTS-Warnings" = String, description = "Clauses that still failed the decode check after retrying, as clause:issue pairs"))),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 406, description = "None of the accepted media types is supported"),
+        (status = 422, description = "Text cannot be synthesized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
     ),
     tag = "TTS API"
 )]
 #[actix_web::get("/api/tts")]
-pub async fn api_tts(data: web::Data<Arc<RwLock<AppState>>>, query: web::Query<TTSQuery>) -> HttpResponse {
-    let start_time = Local::now();
-    let text = query.text.clone();
+pub async fn api_tts(data: web::Data<AppState>, req: HttpRequest, query: web::Query<TTSQuery>) -> Result<HttpResponse, AppError> {
+    let request = TTSRequest::from(query.into_inner());
+    synthesize_request(data, &req, request).await
+}
 
-    // Synthesize speech while holding the read lock only temporarily
-    let wav = {
-        let app_state = data.read().await;  // Acquire read lock
-        app_state.engine.synthesis(&text, 0.2) // Call `synthesis` synchronously
-    };
+#[utoipa::path(
+    post,
+    path = "/api/tts",
+    request_body = TTSRequest,
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class, interactive by default")),
+    responses(
+        (status = 200, description = "Successfully got tts response, encoded as wav, pcm, mulaw (audio/PCMU), alaw (audio/PCMA), flac (audio/flac) or ogg (audio/ogg; codecs=vorbis)", content_type = "audio/wav",
+            headers(("X-TTS-Warnings" = String, description = "Clauses that still failed the decode check after retrying, as clause:issue pairs"))),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 406, description = "None of the accepted media types is supported"),
+        (status = 422, description = "Text cannot be synthesized", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+        (status = 503, description = "Synthesis queue is full, retry after the number of seconds in Retry-After", body = ErrorResponse)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::post("/api/tts")]
+pub async fn api_tts_post(data: web::Data<AppState>, req: HttpRequest, body: web::Json<TTSRequest>) -> Result<HttpResponse, AppError> {
+    synthesize_request(data, &req, body.into_inner()).await
+}
 
-    let mut cursor = Cursor::new(Vec::new());
-    let mut writer = hound::WavWriter::new(&mut cursor, hound::WavSpec{
-        channels:   1,
-        sample_rate: 24000,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    }).expect("Failed to write sample to WAV.");
-    for &sample in &wav {
-        writer.write_sample(sample).expect("Failed to write sample to WAV.");
-    }
-    writer.finalize().unwrap();
+async fn synthesize_request(data: web::Data<AppState>, req: &HttpRequest, request: TTSRequest) -> Result<HttpResponse, AppError> {
+    let format = resolve_format(req, request.format)?;
+    let priority = resolve_priority(req, &data, Priority::Interactive)?;
+    let engine = data.engine();
+    let params = request.to_params(format, &engine)?;
+    synthesize(data, engine, request.text, params, format, priority).await
+}
+
+async fn synthesize(
+    data: web::Data<AppState>,
+    engine: Arc<TTSEngine>,
+    text: String,
+    params: SynthesisParams,
+    format: AudioFormat,
+    priority: Priority,
+) -> Result<HttpResponse, AppError> {
+    let start_time = Local::now();
+
+    // 推理与编码在合成线程池中执行，队列已满时返回 503
+    let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+    let req_text = text.clone();
+    let (body, warnings) = data
+        .pool
+        .run(priority, move || -> Result<(Vec<u8>, String), AppError> {
+            let synthesis = engine
+                .synthesis_with_report(&req_text, &params)
+                .inspect_err(|e| warn!("req: {:?} failed: {}", req_text, e))?;
+            let body = audio::encode(&synthesis.audio, sample_rate, format)?;
+            Ok((body, format_warnings(&synthesis.warnings)))
+        })
+        .await??;
 
     let duration = Local::now().signed_duration_since(start_time);
     // Write to track log
-    {
-        let mut app_state = data.write().await;  // Acquire write lock
-        app_state.track.record_query(
-            text.clone(),
-            start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
-            std::time::Duration::from_millis(duration.num_milliseconds() as u64),
-        );
-    }
+    data.track.lock().unwrap().record_query(
+        text.clone(),
+        start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        std::time::Duration::from_millis(duration.num_milliseconds() as u64),
+    );
     info!("req: {:?} cost: {:.2}s", text, duration.num_milliseconds() as f64 / 1000.0);
 
-    HttpResponse::Ok().content_type("audio/wav").body(cursor.into_inner())
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type(sample_rate));
+    if !warnings.is_empty() {
+        response.insert_header((WARNINGS_HEADER, warnings));
+    }
+    Ok(response.body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, ResponseError};
+    use super::super::batch_handler::BatchRequest;
+    use super::super::job_handler::JobRequest;
+    use super::super::marks_handler::MarksRequest;
+    use super::super::mel_handler::MelRequest;
+    use super::super::ws_handler::WsRequest;
+
+    // 嵌入 TTSRequest 的请求体中拼错的字段应返回 400，与 /api/tts 一致
+    #[test]
+    fn test_unknown_fields() {
+        let status = |result: Result<(), AppError>| result.map_err(|e| e.status_code()).err();
+        assert!(serde_json::from_str::<TTSRequest>(r#"{"text": "你好", "sped": 1.2}"#).is_err());
+
+        let batch: BatchRequest = serde_json::from_str(r#"{"items": [{"id": "a", "text": "你好", "speed": 1.2}]}"#).unwrap();
+        assert_eq!(batch.items[0].request.speed, Some(1.2));
+        assert_eq!(status(batch.validate()), None);
+        let batch: BatchRequest = serde_json::from_str(r#"{"items": [{"id": "a", "text": "你好", "sped": 1.2}]}"#).unwrap();
+        assert_eq!(status(batch.validate()), Some(StatusCode::BAD_REQUEST));
+
+        let job: JobRequest = serde_json::from_str(r#"{"text": "你好", "callback_url": "https://example.com"}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&job.unknown)), None);
+        let job: JobRequest = serde_json::from_str(r#"{"text": "你好", "callbak_url": "https://example.com"}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&job.unknown)), Some(StatusCode::BAD_REQUEST));
+
+        let mel: MelRequest = serde_json::from_str(r#"{"text": "你好", "mel_format": "raw"}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&mel.unknown)), None);
+        let mel: MelRequest = serde_json::from_str(r#"{"text": "你好", "mel_fromat": "raw"}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&mel.unknown)), Some(StatusCode::BAD_REQUEST));
+
+        let marks: MarksRequest = serde_json::from_str(r#"{"text": "你好", "include_audio": true}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&marks.unknown)), None);
+        let marks: MarksRequest = serde_json::from_str(r#"{"text": "你好", "include_audo": true}"#).unwrap();
+        assert_eq!(status(deny_unknown_fields(&marks.unknown)), Some(StatusCode::BAD_REQUEST));
+
+        let ws: WsRequest = serde_json::from_str(r#"{"type": "synthesize", "id": "1", "text": "你好"}"#).unwrap();
+        let WsRequest::Synthesize { unknown, .. } = ws else { panic!("{:?}", ws) };
+        assert_eq!(status(deny_unknown_fields(&unknown)), None);
+        let ws: WsRequest = serde_json::from_str(r#"{"type": "begin", "text": "", "sped": 1.2}"#).unwrap();
+        let WsRequest::Begin { unknown, .. } = ws else { panic!("{:?}", ws) };
+        assert_eq!(status(deny_unknown_fields(&unknown)), Some(StatusCode::BAD_REQUEST));
+    }
+
+    // 批次的总字符数超过上限时返回 400，提示改用 /api/jobs
+    #[test]
+    fn test_batch_chars() {
+        let batch = |count: usize| -> BatchRequest {
+            let text = "你".repeat(MAX_TEXT_CHARS);
+            let items: Vec<String> = (0..count).map(|i| format!(r#"{{"id": "{}", "text": "{}"}}"#, i, text)).collect();
+            serde_json::from_str(&format!(r#"{{"items": [{}]}}"#, items.join(","))).unwrap()
+        };
+        assert!(batch(10).validate().is_ok());
+        let error = batch(11).validate().unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert!(error.to_string().contains("/api/jobs"), "{}", error);
+    }
 }
@@ -0,0 +1,59 @@
+use actix_web::{web, HttpResponse};
+use super::super::super::AppState;
+use super::super::engine::tts_engine::TextType;
+use super::super::engine::voice::{Voice, VoiceDefaults};
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct SpeakerInfo {
+    /// 说话人名称，可作为请求的 speaker 参数
+    pub name: String,
+    /// 说话人 id
+    pub id: i32,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct VoiceInfo {
+    /// 音色名称，可作为请求的 voice 参数
+    pub name: String,
+    /// 音色的语言
+    pub lang: TextType,
+    /// 模型输出的原生采样率（Hz），合成结果统一转换为引擎采样率
+    pub sample_rate: usize,
+    /// 每帧梅尔谱对应的采样数，见 /api/tts/mel
+    pub hop_size: usize,
+    /// 音色的默认参数
+    pub defaults: VoiceDefaults,
+    /// 可选的说话人
+    pub speakers: Vec<SpeakerInfo>,
+}
+
+impl From<&Voice> for VoiceInfo {
+    fn from(voice: &Voice) -> Self {
+        Self {
+            name: voice.name.clone(),
+            lang: voice.lang,
+            sample_rate: voice.vocoder.sample_rate(),
+            hop_size: voice.vocoder.hop_size(),
+            defaults: voice.defaults.clone(),
+            speakers: voice
+                .speakers
+                .iter()
+                .map(|(name, id)| SpeakerInfo { name: name.clone(), id: *id })
+                .collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/voices",
+    responses(
+        (status = 200, description = "Loaded voices and their speakers", body = Vec<VoiceInfo>)
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::get("/api/voices")]
+pub async fn api_voices(data: web::Data<AppState>) -> HttpResponse {
+    let voices: Vec<VoiceInfo> = data.engine().voices().iter().map(VoiceInfo::from).collect();
+    HttpResponse::Ok().json(voices)
+}
@@ -0,0 +1,504 @@
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, Message, StreamHandler};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use bytes::Bytes;
+use tracing::{self, info, warn};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use super::super::super::AppState;
+use super::super::super::base::pool::{self, Priority};
+use super::super::super::error::{self, AppError};
+use super::super::engine::audio::{self, AudioFormat};
+use super::super::engine::error::TTSError;
+use super::super::engine::incremental::IncrementalSplitter;
+use super::super::engine::tts_engine::{SynthesisParams, TTSEngine};
+use super::tts_handler::{deny_unknown_fields, resolve_priority, TTSRequest, UnknownFields};
+use chrono::Local;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 客户端发送的 JSON 消息
+#[derive(serde::Deserialize, utoipa::ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WsRequest {
+    /// 提交一次合成，字段同 POST /api/tts；id 原样回传，用于区分同一连接上的多个请求
+    Synthesize {
+        id: Option<String>,
+        #[serde(flatten)]
+        request: TTSRequest,
+        #[serde(flatten)]
+        #[schema(ignore)]
+        unknown: UnknownFields,
+    },
+    /// 开始增量输入，字段同 POST /api/tts，其中 text 可为空
+    Begin {
+        id: Option<String>,
+        #[serde(flatten)]
+        request: TTSRequest,
+        #[serde(flatten)]
+        #[schema(ignore)]
+        unknown: UnknownFields,
+    },
+    /// 追加增量文本片段，每凑齐一个分句就立即合成
+    Append { text: String },
+    /// 增量输入结束，合成剩余文本
+    End,
+    /// 取消请求：不带 id 时取消正在合成的请求，带 id 时取消对应的请求
+    Cancel { id: Option<String> },
+}
+
+// 服务端发送的 JSON 事件，音频本身以二进制帧发送
+#[derive(serde::Serialize, utoipa::ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WsEvent {
+    /// 开始合成，随后每个分句一个二进制帧
+    Start {
+        id: Option<String>,
+        sample_rate: usize,
+        format: AudioFormat,
+        /// 分句数，增量输入时未知
+        clauses: Option<usize>,
+    },
+    /// 合成结束（或被取消）
+    Done {
+        id: Option<String>,
+        clauses: usize,
+        cancelled: bool,
+        /// 从开始合成到发送第一个分句的耗时（毫秒）
+        first_chunk_ms: Option<u64>,
+        total_ms: u64,
+        /// 已发送音频的时长（毫秒）
+        audio_ms: u64,
+    },
+    Error {
+        id: Option<String>,
+        /// 与 HTTP 接口错误响应一致的错误码
+        code: u32,
+        message: String,
+    },
+}
+
+impl WsEvent {
+    fn error(id: Option<String>, err: &AppError) -> Self {
+        WsEvent::Error {
+            id,
+            code: error::to_integer(err),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+enum SessionMessage {
+    Event(WsEvent),
+    Audio(Bytes),
+    // 当前请求已结束，可以开始下一个
+    Finished(WsEvent),
+}
+
+enum JobText {
+    Full,
+    // 增量输入的分句，发送端关闭即表示输入结束
+    Incremental(mpsc::Receiver<String>),
+}
+
+struct Job {
+    id: Option<String>,
+    request: TTSRequest,
+    text: JobText,
+}
+
+struct IncrementalInput {
+    id: Option<String>,
+    splitter: IncrementalSplitter,
+    clauses: mpsc::Sender<String>,
+}
+
+struct RunningJob {
+    id: Option<String>,
+    cancel: Arc<AtomicBool>,
+}
+
+pub struct TtsSession {
+    data: web::Data<AppState>,
+    // 连接建立时确定，对该连接上的所有请求生效
+    priority: Priority,
+    queue: VecDeque<Job>,
+    current: Option<RunningJob>,
+    incremental: Option<IncrementalInput>,
+    hb: Instant,
+}
+
+impl TtsSession {
+    pub fn new(data: web::Data<AppState>, priority: Priority) -> Self {
+        Self {
+            data,
+            priority,
+            queue: VecDeque::new(),
+            current: None,
+            incremental: None,
+            hb: Instant::now(),
+        }
+    }
+
+    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+                warn!("websocket client heartbeat failed, disconnecting");
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+
+    fn send_event(ctx: &mut ws::WebsocketContext<Self>, event: &WsEvent) {
+        match serde_json::to_string(event) {
+            Ok(text) => ctx.text(text),
+            Err(err) => warn!("failed to serialize websocket event: {}", err),
+        }
+    }
+
+    fn handle_request(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let request = match serde_json::from_str::<WsRequest>(text) {
+            Ok(request) => request,
+            Err(err) => {
+                Self::send_event(ctx, &WsEvent::error(None, &AppError::InvalidParameter(err.to_string())));
+                return;
+            }
+        };
+        if let WsRequest::Synthesize { id, unknown, .. } | WsRequest::Begin { id, unknown, .. } = &request {
+            if let Err(err) = deny_unknown_fields(unknown) {
+                Self::send_event(ctx, &WsEvent::error(id.clone(), &err));
+                return;
+            }
+        }
+
+        match request {
+            WsRequest::Synthesize { id, request, .. } => {
+                self.queue.push_back(Job { id, request, text: JobText::Full });
+                self.start_next(ctx);
+            }
+            WsRequest::Begin { id, mut request, .. } => {
+                if self.incremental.is_some() {
+                    Self::send_event(ctx, &WsEvent::error(
+                        id,
+                        &AppError::InvalidParameter("an incremental input is already open, send end first".to_string()),
+                    ));
+                    return;
+                }
+                let (tx, rx) = mpsc::channel();
+                let mut input = IncrementalInput { id: id.clone(), splitter: IncrementalSplitter::new(), clauses: tx };
+                for clause in input.splitter.push(&std::mem::take(&mut request.text)) {
+                    let _ = input.clauses.send(clause);
+                }
+                self.incremental = Some(input);
+                self.queue.push_back(Job { id, request, text: JobText::Incremental(rx) });
+                self.start_next(ctx);
+            }
+            WsRequest::Append { text } => match self.incremental.as_mut() {
+                Some(input) => {
+                    for clause in input.splitter.push(&text) {
+                        let _ = input.clauses.send(clause);
+                    }
+                }
+                None => Self::send_event(ctx, &WsEvent::error(
+                    None,
+                    &AppError::InvalidParameter("append without begin".to_string()),
+                )),
+            },
+            WsRequest::End => match self.incremental.take() {
+                // 丢弃发送端即通知合成线程输入已结束
+                Some(mut input) => {
+                    if let Some(clause) = input.splitter.finish() {
+                        let _ = input.clauses.send(clause);
+                    }
+                }
+                None => Self::send_event(ctx, &WsEvent::error(
+                    None,
+                    &AppError::InvalidParameter("end without begin".to_string()),
+                )),
+            },
+            WsRequest::Cancel { id } => {
+                if self.incremental.as_ref().is_some_and(|input| id.is_none() || input.id == id) {
+                    self.incremental = None;
+                }
+                let queued = self.queue.len();
+                if id.is_some() {
+                    self.queue.retain(|job| job.id != id);
+                }
+                if let Some(current) = &self.current {
+                    if id.is_none() || current.id == id {
+                        current.cancel.store(true, Ordering::SeqCst);
+                    }
+                }
+                // 排队中被取消的请求直接回复 done
+                if self.queue.len() < queued {
+                    Self::send_event(ctx, &WsEvent::Done {
+                        id,
+                        clauses: 0,
+                        cancelled: true,
+                        first_chunk_ms: None,
+                        total_ms: 0,
+                        audio_ms: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    fn start_next(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        if self.current.is_some() {
+            return;
+        }
+        if let Some(job) = self.queue.pop_front() {
+            let cancel = Arc::new(AtomicBool::new(false));
+            self.current = Some(RunningJob { id: job.id.clone(), cancel: cancel.clone() });
+            run_job(ctx.address(), self.data.clone(), self.priority, job, cancel);
+        }
+    }
+}
+
+// 在合成线程池中流式合成一段文本，音频块经通道交回当前线程；on_chunk 返回 false 时停止合成
+fn stream_on_pool<F>(
+    data: &AppState,
+    engine: &Arc<TTSEngine>,
+    priority: Priority,
+    text: String,
+    params: SynthesisParams,
+    mut on_chunk: F,
+) -> Result<(), AppError>
+where
+    F: FnMut(Vec<i16>) -> bool,
+{
+    let (tx, rx) = mpsc::channel();
+    let engine = engine.clone();
+    let result = data
+        .pool
+        .submit(priority, move || engine.synthesis_stream(&text, &params, |chunk| tx.send(chunk).is_ok()))?;
+    // 提前退出时丢弃接收端，合成线程在下一个分句后停止
+    for chunk in rx {
+        if !on_chunk(chunk) {
+            break;
+        }
+    }
+    Ok(result.blocking_recv().map_err(|_| pool::task_aborted())??)
+}
+
+// 在阻塞线程中等待输入并调度合成，推理在合成线程池中执行，结果发回会话
+fn run_job(addr: Addr<TtsSession>, data: web::Data<AppState>, priority: Priority, job: Job, cancel: Arc<AtomicBool>) {
+    tokio::task::spawn_blocking(move || {
+        let start = Instant::now();
+        let start_time = Local::now();
+        let Job { id, mut request, text } = job;
+        let format = request.format.unwrap_or_default();
+        // 整个任务使用同一个引擎，期间重新加载不影响该任务
+        let engine = data.engine();
+
+        // 增量输入的 text 在 begin 时可以为空，只校验其余参数
+        let params = match text {
+            JobText::Full => request.to_params(format, &engine),
+            JobText::Incremental(_) => request.options_to_params(format, &engine),
+        };
+        let params = match params {
+            Ok(params) => params,
+            Err(err) => {
+                addr.do_send(SessionMessage::Finished(WsEvent::error(id, &err)));
+                return;
+            }
+        };
+        let sample_rate = params.sample_rate.unwrap_or(engine.sample_rate());
+        let clauses = match text {
+            JobText::Full => match engine.clauses(&request.text, &params) {
+                Ok(clauses) => Some(clauses.len()),
+                Err(err) => {
+                    addr.do_send(SessionMessage::Finished(WsEvent::error(id, &err.into())));
+                    return;
+                }
+            },
+            JobText::Incremental(_) => None,
+        };
+        addr.do_send(SessionMessage::Event(WsEvent::Start {
+            id: id.clone(),
+            sample_rate,
+            format,
+            clauses,
+        }));
+
+        let mut first_chunk_ms = None;
+        let mut sent = 0;
+        let mut samples = 0;
+        let mut encode_error = None;
+        let mut on_chunk = |chunk: Vec<i16>| {
+            if cancel.load(Ordering::SeqCst) {
+                return false;
+            }
+            // wav、flac、ogg 格式下每一帧都是独立完整的文件，便于浏览器直接解码播放
+            let frame = match audio::encode(&chunk, sample_rate, format) {
+                Ok(frame) => frame,
+                Err(err) => {
+                    encode_error = Some(err);
+                    return false;
+                }
+            };
+            first_chunk_ms.get_or_insert(start.elapsed().as_millis() as u64);
+            sent += 1;
+            samples += chunk.len();
+            addr.do_send(SessionMessage::Audio(Bytes::from(frame)));
+            !cancel.load(Ordering::SeqCst)
+        };
+
+        let result = match text {
+            JobText::Full => stream_on_pool(&data, &engine, priority, request.text.clone(), params, &mut on_chunk),
+            JobText::Incremental(rx) => {
+                let sil_time = params.sil_time.unwrap_or(engine.pauses().comma);
+                let silence = vec![0i16; (sil_time * sample_rate as f32) as usize];
+                let mut texts = Vec::new();
+                let mut result = Ok(());
+                // 每个分句单独提交到线程池，等待文本期间不占用合成线程
+                for clause in rx.iter() {
+                    if cancel.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let mut first = texts.is_empty();
+                    let clause_result = stream_on_pool(&data, &engine, priority, clause.clone(), params.clone(), |chunk| {
+                        if first {
+                            first = false;
+                            return on_chunk(chunk);
+                        }
+                        let mut with_silence = silence.clone();
+                        with_silence.extend_from_slice(&chunk);
+                        on_chunk(with_silence)
+                    });
+                    match clause_result {
+                        // 只有标点等无法朗读的分句直接跳过
+                        Err(AppError::Synthesis(TTSError::EmptyText)) => continue,
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                        Ok(()) => texts.push(clause),
+                    }
+                }
+                request.text = texts.join("，");
+                result
+            }
+        };
+        let result = match encode_error {
+            Some(err) => Err(err.into()),
+            None => result,
+        };
+        if let Err(err) = result {
+            warn!("ws req: {:?} failed: {}", request.text, err);
+            addr.do_send(SessionMessage::Finished(WsEvent::error(id, &err)));
+            return;
+        }
+        let cancelled = cancel.load(Ordering::SeqCst) || clauses.is_some_and(|clauses| sent < clauses);
+
+        let total_ms = start.elapsed().as_millis() as u64;
+        data.track.lock().unwrap().record_query(
+            request.text.clone(),
+            start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Duration::from_millis(total_ms),
+        );
+        info!("ws req: {:?} cost: {:.2}s", request.text, total_ms as f64 / 1000.0);
+
+        addr.do_send(SessionMessage::Finished(WsEvent::Done {
+            id,
+            clauses: sent,
+            cancelled,
+            first_chunk_ms,
+            total_ms,
+            audio_ms: (samples * 1000 / sample_rate) as u64,
+        }));
+    });
+}
+
+impl Actor for TtsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.heartbeat(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(current) = &self.current {
+            current.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Handler<SessionMessage> for TtsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionMessage, ctx: &mut Self::Context) {
+        match msg {
+            SessionMessage::Event(event) => Self::send_event(ctx, &event),
+            SessionMessage::Audio(bytes) => ctx.binary(bytes),
+            SessionMessage::Finished(event) => {
+                Self::send_event(ctx, &event);
+                self.current = None;
+                self.start_next(ctx);
+            }
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for TtsSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(err) => {
+                warn!("websocket protocol error: {}", err);
+                ctx.stop();
+                return;
+            }
+        };
+
+        match msg {
+            ws::Message::Ping(msg) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            ws::Message::Pong(_) => {
+                self.hb = Instant::now();
+            }
+            ws::Message::Text(text) => {
+                self.hb = Instant::now();
+                self.handle_request(&text, ctx);
+            }
+            ws::Message::Binary(_) => {
+                Self::send_event(ctx, &WsEvent::error(
+                    None,
+                    &AppError::InvalidParameter("binary messages are not supported".to_string()),
+                ));
+            }
+            ws::Message::Close(reason) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/tts",
+    description = "WebSocket synthesis. Send `WsRequest` JSON text frames; every clause comes back as a binary frame, followed by a `WsEvent` done event.",
+    params(("X-Priority" = Option<Priority>, Header, description = "Scheduling class for every request on the connection, interactive by default")),
+    responses(
+        (status = 101, description = "Switching protocols to WebSocket"),
+        (status = 400, description = "Bad request")
+    ),
+    tag = "TTS API"
+)]
+#[actix_web::get("/ws/tts")]
+pub async fn ws_tts(data: web::Data<AppState>, req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, actix_web::Error> {
+    let priority = resolve_priority(&req, &data, Priority::Interactive)?;
+    ws::start(TtsSession::new(data, priority), &req, stream)
+}
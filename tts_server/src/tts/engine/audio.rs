@@ -0,0 +1,369 @@
+use super::error::{Result, TTSError};
+use super::{codec, g711};
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+// 输出音频格式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// 16-bit 单声道 PCM WAV
+    #[default]
+    Wav,
+    /// 无文件头的 16-bit little-endian 单声道 PCM
+    Pcm,
+    /// 无文件头的 G.711 mu-law，默认 8000 Hz
+    #[serde(alias = "ulaw", alias = "pcmu")]
+    Mulaw,
+    /// 无文件头的 G.711 A-law，默认 8000 Hz
+    #[serde(alias = "pcma")]
+    Alaw,
+    /// FLAC 无损压缩
+    Flac,
+    /// Ogg Vorbis 有损压缩
+    #[serde(alias = "vorbis")]
+    Ogg,
+}
+
+impl AudioFormat {
+    pub fn content_type(&self, sample_rate: usize) -> String {
+        match self {
+            AudioFormat::Wav => "audio/wav".to_string(),
+            AudioFormat::Pcm => format!("audio/pcm;rate={};bits=16;channels=1", sample_rate),
+            AudioFormat::Mulaw => format!("audio/PCMU;rate={}", sample_rate),
+            AudioFormat::Alaw => format!("audio/PCMA;rate={}", sample_rate),
+            AudioFormat::Flac => "audio/flac".to_string(),
+            AudioFormat::Ogg => "audio/ogg;codecs=vorbis".to_string(),
+        }
+    }
+
+    // 保存为文件时使用的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Pcm => "pcm",
+            AudioFormat::Mulaw => "ulaw",
+            AudioFormat::Alaw => "alaw",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Ogg => "ogg",
+        }
+    }
+
+    // 未指定采样率时该格式使用的采样率，None 表示沿用引擎采样率
+    pub fn default_sample_rate(&self) -> Option<usize> {
+        match self {
+            AudioFormat::Mulaw | AudioFormat::Alaw => Some(8000),
+            _ => None,
+        }
+    }
+
+    // 能否逐分句编码后直接拼接输出，压缩格式需要完整音频才能编码
+    pub fn is_streamable(&self) -> bool {
+        !matches!(self, AudioFormat::Flac | AudioFormat::Ogg)
+    }
+
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "audio/wav" | "audio/wave" | "audio/x-wav" | "audio/vnd.wave" => Some(AudioFormat::Wav),
+            "audio/pcm" => Some(AudioFormat::Pcm),
+            "audio/pcmu" | "audio/basic" => Some(AudioFormat::Mulaw),
+            "audio/pcma" => Some(AudioFormat::Alaw),
+            "audio/flac" | "audio/x-flac" => Some(AudioFormat::Flac),
+            "audio/ogg" | "audio/vorbis" | "application/ogg" => Some(AudioFormat::Ogg),
+            _ => None,
+        }
+    }
+
+    // 根据 Accept 请求头选择输出格式：取 q 值最高的受支持类型，
+    // 只包含通配符时返回默认格式，全部不受支持时返回 None
+    pub fn negotiate(accept: &str) -> Option<Self> {
+        let mut best: Option<(f32, Self)> = None;
+        for item in accept.split(',') {
+            let mut parts = item.split(';');
+            let mime = parts.next().unwrap_or_default().trim();
+            if mime.is_empty() {
+                continue;
+            }
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+            let format = match mime {
+                "*/*" | "audio/*" => Some(AudioFormat::default()),
+                _ => Self::from_mime(mime),
+            };
+            if let Some(format) = format {
+                if best.is_none_or(|(q, _)| quality > q) {
+                    best = Some((quality, format));
+                }
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+}
+
+// 使用 rubato 对单声道音频进行重采样
+pub fn resample(audio: Vec<f32>, from_rate: usize, to_rate: usize) -> Result<Vec<f32>> {
+    if from_rate == to_rate || audio.is_empty() {
+        return Ok(audio);
+    }
+
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+    let mut resampler = SincFixedIn::<f32>::new(
+        to_rate as f64 / from_rate as f64,
+        2.0,
+        params,
+        audio.len(),
+        1,
+    )
+    .map_err(|e| TTSError::Audio(format!("resampler {} -> {} Hz: {}", from_rate, to_rate, e)))?;
+    let converted_data: Vec<Vec<f32>> = vec![audio; 1];
+    let res_audio = resampler
+        .process(&converted_data, None)
+        .map_err(|e| TTSError::Audio(format!("resample {} -> {} Hz: {}", from_rate, to_rate, e)))?;
+    Ok(res_audio.into_iter().flatten().collect())
+}
+
+// WSOLA 变速不变调：按 rate 倍速播放，rate > 1 变快。
+// 每帧在名义位置附近搜索与上一帧自然延续最相似的位置再重叠相加，避免相位错乱
+pub fn time_stretch(samples: &[i16], rate: f32, sample_rate: usize) -> Vec<i16> {
+    let frame_len = sample_rate * 30 / 1000;
+    let hop_out = frame_len / 2;
+    let tolerance = sample_rate * 10 / 1000;
+    if rate == 1.0 || samples.len() < frame_len * 2 {
+        return samples.to_vec();
+    }
+
+    let input = i16_to_f32(samples);
+    let window: Vec<f32> = (0..frame_len)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / frame_len as f32).cos())
+        .collect();
+    let out_len = (input.len() as f32 / rate) as usize;
+    let mut output = vec![0.0f32; out_len + frame_len];
+    let mut norm = vec![0.0f32; out_len + frame_len];
+
+    let mut prev = 0;
+    for k in 0.. {
+        let out_pos = k * hop_out;
+        if out_pos >= out_len {
+            break;
+        }
+        let nominal = ((k as f32 * hop_out as f32 * rate) as usize).min(input.len() - frame_len);
+        let pos = if k == 0 {
+            0
+        } else {
+            // 上一帧在输入中的自然延续
+            let natural = (prev + hop_out).min(input.len() - frame_len);
+            let reference = &input[natural..natural + hop_out];
+            let low = nominal.saturating_sub(tolerance);
+            let high = (nominal + tolerance).min(input.len() - frame_len);
+            (low..=high)
+                .map(|candidate| {
+                    let score: f32 = reference
+                        .iter()
+                        .zip(&input[candidate..candidate + hop_out])
+                        .map(|(a, b)| a * b)
+                        .sum();
+                    (candidate, score)
+                })
+                .fold((nominal, f32::MIN), |best, item| if item.1 > best.1 { item } else { best })
+                .0
+        };
+        for i in 0..frame_len {
+            output[out_pos + i] += input[pos + i] * window[i];
+            norm[out_pos + i] += window[i];
+        }
+        prev = pos;
+    }
+
+    output.truncate(out_len);
+    let output: Vec<f32> = output
+        .iter()
+        .zip(&norm)
+        .map(|(&x, &w)| if w > 1e-3 { x / w } else { x })
+        .collect();
+    f32_to_i16(&output)
+}
+
+// 线性增益，超出范围的采样削波
+pub fn apply_gain(samples: &mut [i16], gain: f32) {
+    for sample in samples.iter_mut() {
+        *sample = (*sample as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+    }
+}
+
+pub fn f32_to_i16(audio: &[f32]) -> Vec<i16> {
+    audio.iter().map(|&x| (x * i16::MAX as f32) as i16).collect()
+}
+
+pub fn i16_to_f32(audio: &[i16]) -> Vec<f32> {
+    audio.iter().map(|&x| x as f32 / i16::MAX as f32).collect()
+}
+
+// 按指定格式编码音频
+pub fn encode(samples: &[i16], sample_rate: usize, format: AudioFormat) -> Result<Vec<u8>> {
+    match format {
+        AudioFormat::Wav => encode_wav(samples, sample_rate),
+        AudioFormat::Flac => codec::encode_flac(samples, sample_rate),
+        AudioFormat::Ogg => codec::encode_vorbis(samples, sample_rate),
+        _ => encode_chunk(samples, format),
+    }
+}
+
+// 编码流式输出中的一段音频，wav 格式只输出 PCM 数据，文件头需单独发送；
+// 只适用于 is_streamable 的格式，其余格式返回错误
+pub fn encode_chunk(samples: &[i16], format: AudioFormat) -> Result<Vec<u8>> {
+    match format {
+        AudioFormat::Wav | AudioFormat::Pcm => Ok(pcm_bytes(samples)),
+        AudioFormat::Mulaw => Ok(g711::encode_ulaw(samples)),
+        AudioFormat::Alaw => Ok(g711::encode_alaw(samples)),
+        AudioFormat::Flac | AudioFormat::Ogg => {
+            Err(TTSError::Audio(format!("{:?} cannot be encoded in chunks", format)))
+        }
+    }
+}
+
+pub fn pcm_bytes(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+}
+
+// 16-bit 单声道 WAV 文件头；data_len 为 None 时写入开放长度，用于流式输出
+pub fn wav_header(sample_rate: usize, data_len: Option<u32>) -> Vec<u8> {
+    let data_len = data_len.unwrap_or(u32::MAX - 36);
+    let byte_rate = sample_rate as u32 * 2;
+
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(data_len + 36).to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // channels
+    header.extend_from_slice(&(sample_rate as u32).to_le_bytes());
+    header.extend_from_slice(&byte_rate.to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes()); // block align
+    header.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+fn encode_wav(samples: &[i16], sample_rate: usize) -> Result<Vec<u8>> {
+    let wav_error = |e: hound::Error| TTSError::Audio(format!("failed to write wav: {}", e));
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(
+        &mut cursor,
+        hound::WavSpec {
+            channels: 1,
+            sample_rate: sample_rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        },
+    )
+    .map_err(wav_error)?;
+    for &sample in samples {
+        writer.write_sample(sample).map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)?;
+    Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_length() {
+        let audio = vec![0.0f32; 22050];
+        let out = resample(audio, 22050, 24000).unwrap();
+        assert!((out.len() as i64 - 24000).abs() < 256);
+        let same = resample(vec![0.5; 100], 24000, 24000).unwrap();
+        assert_eq!(same.len(), 100);
+    }
+
+    #[test]
+    fn test_encode_wav() {
+        let wav = encode(&[0, 1, -1, i16::MAX], 16000, AudioFormat::Wav).unwrap();
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 8);
+        let reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+    }
+
+    #[test]
+    fn test_wav_header() {
+        let samples = [3i16, -3, 100];
+        let mut wav = wav_header(24000, Some(6));
+        wav.extend(pcm_bytes(&samples));
+        assert_eq!(wav, encode(&samples, 24000, AudioFormat::Wav).unwrap());
+
+        let open = wav_header(24000, None);
+        assert_eq!(&open[4..8], &u32::MAX.to_le_bytes());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(AudioFormat::negotiate("audio/wav"), Some(AudioFormat::Wav));
+        assert_eq!(AudioFormat::negotiate("*/*"), Some(AudioFormat::Wav));
+        assert_eq!(
+            AudioFormat::negotiate("audio/wav;q=0.5, audio/PCMU;rate=8000"),
+            Some(AudioFormat::Mulaw)
+        );
+        assert_eq!(
+            AudioFormat::negotiate("audio/pcma;q=0.9, audio/*;q=0.1"),
+            Some(AudioFormat::Alaw)
+        );
+        assert_eq!(AudioFormat::negotiate("audio/pcm;q=0, text/html"), None);
+        assert_eq!(AudioFormat::negotiate("application/json"), None);
+        assert_eq!(
+            AudioFormat::negotiate("audio/ogg;codecs=vorbis, audio/flac;q=0.8"),
+            Some(AudioFormat::Ogg)
+        );
+        assert_eq!(AudioFormat::negotiate("audio/x-flac"), Some(AudioFormat::Flac));
+    }
+
+    #[test]
+    fn test_time_stretch() {
+        let tone: Vec<i16> = (0..24000)
+            .map(|i| ((i as f32 * 220.0 * 2.0 * std::f32::consts::PI / 24000.0).sin() * 10000.0) as i16)
+            .collect();
+        let fast = time_stretch(&tone, 2.0, 24000);
+        assert_eq!(fast.len(), 12000);
+        let slow = time_stretch(&tone, 0.5, 24000);
+        assert_eq!(slow.len(), 48000);
+        // 幅度基本保持
+        let peak = slow[1000..].iter().map(|x| x.abs()).max().unwrap();
+        assert!((9000..=11000).contains(&peak));
+        assert_eq!(time_stretch(&tone, 1.0, 24000), tone);
+    }
+
+    #[test]
+    fn test_apply_gain() {
+        let mut samples = [100i16, -100, 20000, -20000];
+        apply_gain(&mut samples, 2.0);
+        assert_eq!(samples, [200, -200, i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn test_encode_g711() {
+        assert_eq!(encode(&[0, 0], 8000, AudioFormat::Mulaw).unwrap(), vec![0xFF, 0xFF]);
+        assert_eq!(encode(&[0, 0], 8000, AudioFormat::Alaw).unwrap(), vec![0xD5, 0xD5]);
+        assert_eq!(encode(&[1, -2], 16000, AudioFormat::Pcm).unwrap(), vec![1, 0, 0xFE, 0xFF]);
+        assert!(encode_chunk(&[0, 0], AudioFormat::Flac).is_err());
+        assert!(encode_chunk(&[0, 0], AudioFormat::Ogg).is_err());
+    }
+}
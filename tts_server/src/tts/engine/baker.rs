@@ -1,4 +1,7 @@
 use super::cn_tn::NSWNormalizer;
+use super::error::{Result, TTSError};
+use super::model::TextProcessor;
+use super::timing::TextUnit;
 use lazy_static::lazy_static;
 // use pinyin::*;
 use pinyin_translator::*;
@@ -6,11 +9,13 @@ use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Error, Read};
+use std::io::{Error, ErrorKind, Read};
 
 // Define a regex pattern for Chinese characters
 lazy_static! {
     pub static ref ZH_PATTERN: Regex = Regex::new(r"[\u4e00-\u9fa5]").unwrap();
+    // 指定读音的汉字：{汉字|pin1 yin1}
+    pub static ref PINYIN_MARKUP: Regex = Regex::new(r"\{([^{}|]*)\|([^{}]*)\}").unwrap();
 }
 
 // Define a function to check if a string contains Chinese characters
@@ -35,19 +40,26 @@ pub struct BakerProcessor {
 impl BakerProcessor {
     // Define the __post_init__ method
     pub fn new() -> Result<Self, Error> {
+        Self::with_mapper("assets/baker_mapper.json")
+    }
+
+    // 从指定的 mapper 文件加载音素表与说话人
+    pub fn with_mapper(mapper_path: &str) -> Result<Self, Error> {
         // Implement the method
         let mut processor = Self {
             pinyin_dict: HashMap::new(),
             symbols: Vec::new(),
             speakers_map: HashMap::new(),
-            loaded_mapper_path: Some("assets/baker_mapper.json".to_string()),
+            loaded_mapper_path: Some(mapper_path.to_string()),
             symbol_to_id: HashMap::new(),
             id_to_symbol: HashMap::new(),
             processor_name: None,
             eos_id: 0,
         };
 
-        processor.load_mapper().unwrap();
+        processor
+            .load_mapper()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", mapper_path, e)))?;
         if !processor.setup_eos_token().is_empty() {
             processor.add_symbol(processor.setup_eos_token());
             processor.eos_id = processor.symbol_to_id[&processor.setup_eos_token()];
@@ -93,7 +105,7 @@ impl BakerProcessor {
                     .get(pinyin_i_clone.trim_end_matches(char::is_numeric))
                 {
                     if let Some(tone) = pinyin_i_clone.chars().last() {
-                        let a = &pinyin_i_clone[..pinyin_i_clone.len() - 1];
+                        let a = pinyin_i_clone.get(..pinyin_i_clone.len() - 1).unwrap_or_default();
                         if let Some((a1, a2)) = self.pinyin_dict.get(a) {
                             result.push(a1.to_string());
                             result.push(format!("{}{}", a2, tone));
@@ -101,7 +113,12 @@ impl BakerProcessor {
                     }
                 } else {
                     if let Some(tone) = pinyin_i_clone.chars().last() {
-                        let a = &pinyin_i_clone[..pinyin_i_clone.len() - 2];
+                        // 儿化音去掉末尾的 r 与声调；标注的拼音可能过短或不是 ASCII，不能直接切片
+                        let a = pinyin_i_clone
+                            .len()
+                            .checked_sub(2)
+                            .and_then(|end| pinyin_i_clone.get(..end))
+                            .unwrap_or_default();
                         if let Some((a1, a2)) = self.pinyin_dict.get(a) {
                             result.push(a1.to_string());
                             result.push(format!("{}{}", a2, tone));
@@ -131,11 +148,32 @@ impl BakerProcessor {
     }
 
     fn text_to_phone(&self, text: &str) -> (String, String) {
-        let normalized_text = NSWNormalizer::new(text).normalize().to_owned();
-
         // let pinyin_with_tone = to_pinyin_vec(normalized_text.as_str(), Pinyin::with_tone_num_end);
         let pt = PinyinTranslator::new();
-        let pinyin_with_tone = pt.translate_as_slice(normalized_text.clone());
+        let mut normalized_text = String::new();
+        let mut pinyin_with_tone = Vec::new();
+        let push_plain = |segment: &str, normalized_text: &mut String, pinyin_with_tone: &mut Vec<String>| {
+            if segment.is_empty() {
+                return;
+            }
+            let normalized = NSWNormalizer::new(segment).normalize().to_owned();
+            // 不含汉字的片段没有拼音，标注之间的标点或空白不能交给 PinyinTranslator
+            if ZH_PATTERN.is_match(&normalized) {
+                pinyin_with_tone.extend(pt.translate_as_slice(normalized.clone()));
+            }
+            normalized_text.push_str(&normalized);
+        };
+
+        // {汉字|pin1 yin1} 形式的片段直接使用给定的拼音，其余文本先做规范化再转拼音
+        let mut last = 0;
+        for caps in PINYIN_MARKUP.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            push_plain(&text[last..whole.start()], &mut normalized_text, &mut pinyin_with_tone);
+            normalized_text.push_str(&caps[1]);
+            pinyin_with_tone.extend(caps[2].split_whitespace().map(String::from));
+            last = whole.end();
+        }
+        push_plain(&text[last..], &mut normalized_text, &mut pinyin_with_tone);
         // println!("\npinyin_with_tone: {:?}", pinyin_with_tone);
 
         let phonemes = self.get_phoneme_from_char_and_pinyin(&normalized_text, pinyin_with_tone);
@@ -145,18 +183,51 @@ impl BakerProcessor {
         (normalized_text.to_string(), phones)
     }
 
-    pub fn text_to_sequence(&self, text: &str, _inference: bool) -> Vec<i32> {
-        let (_, phones) = self.text_to_phone(text);
+    pub fn text_to_sequence(&self, text: &str, _inference: bool) -> Result<Vec<i32>> {
+        Ok(self.text_to_units(text)?.0)
+    }
 
-        let mut sequence: Vec<i32> = phones
-            .split_whitespace()
-            .map(|symbol| self.symbol_to_id[symbol] as i32)
-            .collect();
+    // 与 text_to_sequence 相同，同时返回每个汉字对应的音素区间
+    pub fn text_to_units(&self, text: &str) -> Result<(Vec<i32>, Vec<TextUnit>)> {
+        let (normalized_text, phones) = self.text_to_phone(text);
+        let phones: Vec<&str> = phones.split_whitespace().collect();
+
+        let mut sequence = phones
+            .iter()
+            .map(|symbol| match self.symbol_to_id.get(*symbol) {
+                Some(&id) => Ok(id as i32),
+                None => Err(TTSError::UnknownSymbol {
+                    symbol: symbol.to_string(),
+                    text: text.to_string(),
+                }),
+            })
+            .collect::<Result<Vec<i32>>>()?;
 
         // Add eos tokens
         sequence.push(self.eos_id as i32);
 
-        sequence
+        // 汉字之间以 #0 等韵律标记分隔，首尾为 sil
+        let mut chars = normalized_text.chars().filter(|ch| is_zh(ch.to_string().as_str()));
+        let mut units = Vec::new();
+        let mut start = None;
+        for (i, phone) in phones.iter().enumerate() {
+            let boundary = *phone == "sil" || phone.starts_with('#');
+            match (boundary, start) {
+                (true, Some(begin)) => {
+                    if let Some(ch) = chars.next() {
+                        units.push(TextUnit {
+                            text: ch.to_string(),
+                            tokens: begin..i,
+                        });
+                    }
+                    start = None;
+                }
+                (false, None) => start = Some(i),
+                _ => {}
+            }
+        }
+
+        Ok((sequence, units))
     }
 
     fn add_symbol(&mut self, symbol: String) {
@@ -189,12 +260,12 @@ impl BakerProcessor {
                 serde_json::from_value(id_to_symbol.clone())?;
             self.id_to_symbol = id_to_symbol_map
                 .iter()
-                .map(|(k, v)| (k.parse::<usize>().unwrap(), v.clone()))
-                .collect();
+                .map(|(k, v)| Ok((k.parse::<usize>()?, v.clone())))
+                .collect::<Result<_, std::num::ParseIntError>>()?;
         }
 
         if let Some(processor_name) = parsed_data.get("processor_name") {
-            self.processor_name = Some(processor_name.as_str().unwrap().to_string());
+            self.processor_name = processor_name.as_str().map(String::from);
         }
 
         if let Some(pinyin_dict) = parsed_data.get("pinyin_dict") {
@@ -205,6 +276,16 @@ impl BakerProcessor {
     }
 }
 
+impl TextProcessor for BakerProcessor {
+    fn text_to_units(&self, text: &str) -> Result<(Vec<i32>, Vec<TextUnit>)> {
+        BakerProcessor::text_to_units(self, text)
+    }
+
+    fn speakers(&self) -> &HashMap<String, usize> {
+        &self.speakers_map
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // use pinyin::*;
@@ -297,6 +378,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_text_to_units() {
+        let baker = BakerProcessor::new().unwrap();
+        let (sequence, units) = baker.text_to_units("你好，世界").unwrap();
+        assert_eq!(sequence, baker.text_to_sequence("你好，世界", true).unwrap());
+        let chars: Vec<&str> = units.iter().map(|unit| unit.text.as_str()).collect();
+        assert_eq!(chars, vec!["你", "好", "世", "界"]);
+        // 每个汉字由声母和韵母两个音素组成
+        assert!(units.iter().all(|unit| unit.tokens.len() == 2));
+    }
+
+    #[test]
+    fn test_pinyin_markup() {
+        let baker = BakerProcessor::new().unwrap();
+        // 标注的读音覆盖多音字的默认读音
+        let (norm, phone) = baker.text_to_phone("{重|zhong4}新开始");
+        assert_eq!(norm, "重新开始");
+        assert!(phone.starts_with("sil zh ong4 #0 x in1"));
+        let (_, default_phone) = baker.text_to_phone("重新开始");
+        assert!(default_phone.starts_with("sil ch ong2"));
+        let (norm, _) = baker.text_to_phone("{银行|yin2 hang2}有3个");
+        assert_eq!(norm, "银行有三个");
+    }
+
     #[test]
     fn test_text_to_phone() {
         // let chars = pinyin_translator::vars::CHARS;
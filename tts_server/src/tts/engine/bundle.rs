@@ -0,0 +1,223 @@
+// 音色包：一个 zip 文件包含 manifest.yaml、mapper 与模型文件。
+// 启动时校验格式版本、许可证与每个文件的 SHA-256，解压到音色目录下的 .unpacked 后按普通音色加载
+use super::error::{Result, TTSError};
+use super::voice::VoiceConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST: &str = "manifest.yaml";
+// 支持的音色包格式版本
+pub const BUNDLE_FORMAT: u32 = 1;
+const UNPACK_DIR: &str = ".unpacked";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleManifest {
+    /// 音色包格式版本
+    pub format: u32,
+    /// 音色版本
+    pub version: String,
+    /// 模型的许可证
+    #[serde(alias = "license")]
+    pub licence: String,
+    /// 音色配置，文件路径相对于包的根目录
+    #[serde(flatten)]
+    pub voice: VoiceConfig,
+    /// 包内文件的 SHA-256（十六进制）
+    pub checksums: HashMap<String, String>,
+}
+
+// 校验并解压后的音色包，voice 中的路径已指向解压目录
+#[derive(Debug, Clone)]
+pub struct VoiceBundle {
+    pub path: PathBuf,
+    pub manifest: BundleManifest,
+}
+
+// 加载目录下的所有 .zip 音色包，按文件名排序；任一音色包无效时返回错误
+pub fn load_dir(dir: &Path) -> Result<Vec<VoiceBundle>> {
+    let entries = fs::read_dir(dir).map_err(|e| TTSError::ModelLoad(format!("{}: {}", dir.display(), e)))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "zip"))
+        .collect();
+    paths.sort();
+    let unpack_root = dir.join(UNPACK_DIR);
+    paths.iter().map(|path| open(path, &unpack_root)).collect()
+}
+
+// 校验一个音色包并解压到 unpack_root/<音色名>
+pub fn open(path: &Path, unpack_root: &Path) -> Result<VoiceBundle> {
+    let bundle_error = |message: String| TTSError::ModelLoad(format!("voice bundle {}: {}", path.display(), message));
+
+    let file = File::open(path).map_err(|e| bundle_error(e.to_string()))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| bundle_error(e.to_string()))?;
+    let read = |archive: &mut zip::ZipArchive<File>, name: &str| -> Result<Vec<u8>> {
+        let mut entry = archive
+            .by_name(name)
+            .map_err(|_| bundle_error(format!("missing file {}", name)))?;
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(|e| bundle_error(format!("{}: {}", name, e)))?;
+        Ok(data)
+    };
+
+    let manifest: BundleManifest = serde_yaml::from_slice(&read(&mut archive, MANIFEST)?)
+        .map_err(|e| bundle_error(format!("invalid {}: {}", MANIFEST, e)))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(bundle_error(format!(
+            "unsupported format {}, expected {}",
+            manifest.format, BUNDLE_FORMAT
+        )));
+    }
+    if manifest.version.trim().is_empty() {
+        return Err(bundle_error("version must not be empty".to_string()));
+    }
+    if manifest.licence.trim().is_empty() {
+        return Err(bundle_error("licence must not be empty".to_string()));
+    }
+    if !is_plain_path(&manifest.voice.name) {
+        return Err(bundle_error(format!("invalid voice name {}", manifest.voice.name)));
+    }
+
+    // 音色引用的文件都必须带有校验和
+    let mut voice = manifest.voice.clone();
+    for file in voice.paths_mut() {
+        if !manifest.checksums.contains_key(file.as_str()) {
+            return Err(bundle_error(format!("no checksum for {}", file)));
+        }
+    }
+
+    let target = std::path::absolute(unpack_root.join(&voice.name)).map_err(|e| bundle_error(e.to_string()))?;
+    fs::create_dir_all(&target).map_err(|e| bundle_error(format!("{}: {}", target.display(), e)))?;
+    let mut names: Vec<&String> = manifest.checksums.keys().collect();
+    names.sort();
+    for name in names {
+        if !is_plain_path(name) {
+            return Err(bundle_error(format!("invalid file name {}", name)));
+        }
+        let data = read(&mut archive, name)?;
+        let expected = manifest.checksums[name].to_ascii_lowercase();
+        let actual = sha256_hex(&data);
+        if actual != expected {
+            return Err(bundle_error(format!(
+                "checksum mismatch for {}: expected {}, got {}",
+                name, expected, actual
+            )));
+        }
+        let dest = target.join(name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| bundle_error(format!("{}: {}", parent.display(), e)))?;
+        }
+        fs::write(&dest, data).map_err(|e| bundle_error(format!("{}: {}", dest.display(), e)))?;
+    }
+
+    for file in voice.paths_mut() {
+        *file = target.join(file.as_str()).to_string_lossy().into_owned();
+    }
+    Ok(VoiceBundle {
+        path: path.to_path_buf(),
+        manifest: BundleManifest { voice, ..manifest },
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 只允许包内的相对路径，防止解压到目标目录之外
+fn is_plain_path(name: &str) -> bool {
+    !name.is_empty() && Path::new(name).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    fn manifest(checksums: &[(&str, &[u8])]) -> String {
+        let checksums: Vec<String> = checksums
+            .iter()
+            .map(|(name, data)| format!("  {}: {}", name, sha256_hex(data)))
+            .collect();
+        format!(
+            "format: 1\nversion: 1.0.0\nlicence: CC-BY-4.0\nname: xiaoyu\nlang: Chinese\nprocessor: baker\nmapper: mapper.json\n\
+             acoustic: {{ type: fastspeech2, model: models/fastspeech2.tflite }}\n\
+             vocoder: {{ type: mb_melgan, model: models/mb_melgan.tflite, sample_rate: 24000 }}\n\
+             checksums:\n{}\n",
+            checksums.join("\n")
+        )
+    }
+
+    fn write_bundle(path: &Path, files: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        fs::write(path, writer.finish().unwrap().into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_open_bundle() {
+        let dir = std::env::temp_dir().join(format!("tts_bundles_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let files: [(&str, &[u8]); 3] = [
+            ("mapper.json", b"{}"),
+            ("models/fastspeech2.tflite", b"acoustic"),
+            ("models/mb_melgan.tflite", b"vocoder"),
+        ];
+        let manifest = manifest(&files);
+        let mut archive = vec![(MANIFEST, manifest.as_bytes())];
+        archive.extend_from_slice(&files);
+        write_bundle(&dir.join("xiaoyu.zip"), &archive);
+
+        let bundles = load_dir(&dir).unwrap();
+        assert_eq!(bundles.len(), 1);
+        let mut voice = bundles[0].manifest.voice.clone();
+        assert_eq!(voice.name, "xiaoyu");
+        assert_eq!(bundles[0].manifest.licence, "CC-BY-4.0");
+        let vocoder = voice.paths_mut()[2].clone();
+        assert_eq!(fs::read(&vocoder).unwrap(), b"vocoder");
+
+        // 文件内容与校验和不符
+        archive[3] = ("models/mb_melgan.tflite", b"tampered");
+        write_bundle(&dir.join("xiaoyu.zip"), &archive);
+        let err = load_dir(&dir).unwrap_err().to_string();
+        assert!(err.contains("checksum mismatch for models/mb_melgan.tflite"), "{}", err);
+
+        // 缺少文件
+        archive.pop();
+        write_bundle(&dir.join("xiaoyu.zip"), &archive);
+        let err = load_dir(&dir).unwrap_err().to_string();
+        assert!(err.contains("missing file models/mb_melgan.tflite"), "{}", err);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_manifest_checks() {
+        let dir = std::env::temp_dir().join(format!("tts_bundle_checks_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bad.zip");
+
+        // 模型文件没有校验和
+        let manifest = manifest(&[("mapper.json", b"{}")]);
+        write_bundle(&path, &[(MANIFEST, manifest.as_bytes())]);
+        let err = open(&path, &dir).unwrap_err().to_string();
+        assert!(err.contains("no checksum for models/fastspeech2.tflite"), "{}", err);
+
+        let manifest = manifest.replace("format: 1", "format: 2");
+        write_bundle(&path, &[(MANIFEST, manifest.as_bytes())]);
+        assert!(open(&path, &dir).unwrap_err().to_string().contains("unsupported format 2"));
+
+        assert!(is_plain_path("models/a.tflite"));
+        assert!(!is_plain_path("../a.tflite"));
+        assert!(!is_plain_path("/etc/passwd"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
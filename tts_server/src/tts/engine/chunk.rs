@@ -0,0 +1,150 @@
+// 过长分句的拆分：Tacotron2 在长输入上容易出现注意力漂移、重复或提前停止，
+// 没有标点的长文本按词或韵律边界拆成较短的片段
+use std::ops::Range;
+
+// 默认的分句长度上限（汉字数 + 英文词数）
+pub const DEFAULT_MAX_CLAUSE_LEN: usize = 40;
+// 重试拆分时每个片段至少保留的长度
+const MIN_PIECE_LEN: usize = 2;
+
+// 常位于短语末尾的汉字（助词、连词、介词等），其后是较自然的停顿位置
+const ZH_BREAK_AFTER: &str = "的了着过地得吧呢吗啊和与及或而并且但就都也还又把被对向从在于为";
+// 英文中常引出新短语的词，其前是较自然的停顿位置
+const EN_BREAK_BEFORE: &[&str] = &[
+    "and", "but", "or", "nor", "so", "yet", "because", "although", "though", "while", "when", "where", "which",
+    "who", "whom", "whose", "that", "if", "unless", "until", "since", "after", "before", "as", "with", "without",
+    "for", "from", "to", "of", "in", "on", "at", "by", "about", "into", "through", "during",
+];
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+// 计数单位：一个汉字，或一段不含空白与汉字的字符（英文词、数字等）
+struct Token {
+    range: Range<usize>,
+    cjk: bool,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut word: Option<usize> = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() || is_cjk(c) {
+            if let Some(start) = word.take() {
+                tokens.push(Token { range: start..i, cjk: false });
+            }
+            if is_cjk(c) {
+                tokens.push(Token { range: i..i + c.len_utf8(), cjk: true });
+            }
+        } else if word.is_none() {
+            word = Some(i);
+        }
+    }
+    if let Some(start) = word {
+        tokens.push(Token { range: start..text.len(), cjk: false });
+    }
+    tokens
+}
+
+// 在第 i 个单位之前断开的合适程度：2 为短语边界，1 为词边界，0 为词中间
+fn boundary_score(text: &str, tokens: &[Token], i: usize) -> u8 {
+    let (prev, next) = (&tokens[i - 1], &tokens[i]);
+    let prev_text = &text[prev.range.clone()];
+    let next_text = &text[next.range.clone()];
+    if prev.cjk != next.cjk {
+        return 2;
+    }
+    match prev.cjk {
+        true if ZH_BREAK_AFTER.contains(prev_text) => 2,
+        true => 0,
+        false if EN_BREAK_BEFORE.contains(&next_text.to_ascii_lowercase().as_str()) => 2,
+        false => 1,
+    }
+}
+
+// 分句长度：汉字按字计数，其余按空白分隔的词计数
+pub fn clause_len(text: &str) -> usize {
+    tokenize(text).len()
+}
+
+// 把超过 max_len 的分句拆成不超过 max_len 的片段；
+// 优先在后半段中最合适的边界断开，同样合适时取靠后的位置
+pub fn split_long(text: &str, max_len: usize) -> Vec<String> {
+    let tokens = tokenize(text);
+    if max_len == 0 || tokens.len() <= max_len {
+        return vec![text.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let (mut start, mut byte_start) = (0, 0);
+    while tokens.len() - start > max_len {
+        let candidates = (start + max_len / 2).max(start + 1)..=start + max_len;
+        let cut = candidates
+            .max_by_key(|&i| (boundary_score(text, &tokens, i), i))
+            .expect("non-empty range");
+        let byte_end = tokens[cut].range.start;
+        pieces.push(text[byte_start..byte_end].trim().to_string());
+        (start, byte_start) = (cut, byte_end);
+    }
+    pieces.push(text[byte_start..].trim().to_string());
+    pieces
+}
+
+// 在中间附近最合适的边界一分为二，用于解码失败后的重试；过短时返回 None
+pub fn split_half(text: &str) -> Option<(String, String)> {
+    let tokens = tokenize(text);
+    if tokens.len() < MIN_PIECE_LEN * 2 {
+        return None;
+    }
+    let middle = tokens.len() / 2;
+    let cut = (MIN_PIECE_LEN..=tokens.len() - MIN_PIECE_LEN)
+        .filter(|i| i.abs_diff(middle) <= tokens.len() / 4)
+        .max_by_key(|&i| (boundary_score(text, &tokens, i), std::cmp::Reverse(i.abs_diff(middle))))?;
+    let byte = tokens[cut].range.start;
+    Some((text[..byte].trim().to_string(), text[byte..].trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clause_len() {
+        assert_eq!(clause_len("今天天气很好"), 6);
+        assert_eq!(clause_len("The quick brown fox"), 4);
+        assert_eq!(clause_len("我用iPhone 15拍照"), 6);
+        assert_eq!(clause_len(""), 0);
+    }
+
+    #[test]
+    fn test_split_long() {
+        assert_eq!(split_long("今天天气很好", 10), vec!["今天天气很好"]);
+        assert_eq!(split_long("今天天气很好", 0), vec!["今天天气很好"]);
+
+        // 在助词之后断开
+        let text = "我们明天上午在公司的会议室里讨论下一季度的市场推广计划和预算安排";
+        let pieces = split_long(text, 12);
+        assert_eq!(pieces.concat(), text);
+        assert!(pieces.iter().all(|piece| clause_len(piece) <= 12), "{:?}", pieces);
+        assert_eq!(pieces[0], "我们明天上午在公司的");
+
+        let text = "the committee reviewed the proposal in detail and decided that the budget should be increased for the next year";
+        let pieces = split_long(text, 8);
+        assert!(pieces.iter().all(|piece| clause_len(piece) <= 8), "{:?}", pieces);
+        assert_eq!(pieces.join(" "), text);
+        assert_eq!(pieces[0], "the committee reviewed the proposal in detail");
+    }
+
+    #[test]
+    fn test_split_half() {
+        assert_eq!(split_half("你好"), None);
+        assert_eq!(
+            split_half("今天天气很好我们去公园散步"),
+            Some(("今天天气很好".to_string(), "我们去公园散步".to_string()))
+        );
+        assert_eq!(
+            split_half("I will call you when I get home"),
+            Some(("I will call you".to_string(), "when I get home".to_string()))
+        );
+    }
+}
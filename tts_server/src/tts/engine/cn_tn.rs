@@ -93,6 +93,32 @@ pub struct Digit {
     pub chntext: Option<String>,
 }
 
+// 按万进制读数，超出 u32 范围时逐位读出，避免超长数字导致 panic
+fn count_chinese(digits: &str) -> String {
+    digits
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| {
+            n.to_chinese(ChineseVariant::Simple, ChineseCase::Lower, ChineseCountMethod::TenThousand)
+                .ok()
+        })
+        .unwrap_or_else(|| naive_chinese(digits))
+}
+
+// 逐位读数（不读前导零），超出 u128 范围时按字符逐位转换
+fn naive_chinese(digits: &str) -> String {
+    match digits.parse::<u128>() {
+        Ok(n) => n.to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower),
+        Err(_) => digits
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d) => CHINESE_DIGIS.chars().nth(d as usize).unwrap_or(c),
+                None => c,
+            })
+            .collect(),
+    }
+}
+
 impl Digit {
     pub fn new(digit: String) -> Digit {
         Self {
@@ -126,16 +152,7 @@ impl Digit {
                 if !int_data.is_empty() {
                     chntext = chntext.replace(
                         int_data.as_str(),
-                        int_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese(
-                                ChineseVariant::Simple,
-                                ChineseCase::Lower,
-                                ChineseCountMethod::TenThousand,
-                            )
-                            .unwrap()
-                            .as_str(),
+                        count_chinese(&int_data).as_str(),
                     );
                 }
             }
@@ -155,11 +172,7 @@ impl Digit {
                 if !dec_data.is_empty() {
                     chntext = chntext.replace(
                         dec_data.as_str(),
-                        dec_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                            .as_str(),
+                        naive_chinese(&dec_data).as_str(),
                     );
                 }
             }
@@ -205,29 +218,11 @@ impl Fraction {
                     if !num_data.is_empty() && !den_data.is_empty() {
                         chntext = chntext.replace(
                             num_data.as_str(),
-                            den_data
-                                .parse::<u32>()
-                                .unwrap()
-                                .to_chinese(
-                                    ChineseVariant::Simple,
-                                    ChineseCase::Lower,
-                                    ChineseCountMethod::TenThousand,
-                                )
-                                .unwrap()
-                                .as_str(),
+                            count_chinese(&den_data).as_str(),
                         );
                         chntext = chntext.replace(
                             den_data.as_str(),
-                            num_data
-                                .parse::<u32>()
-                                .unwrap()
-                                .to_chinese(
-                                    ChineseVariant::Simple,
-                                    ChineseCase::Lower,
-                                    ChineseCountMethod::TenThousand,
-                                )
-                                .unwrap()
-                                .as_str(),
+                            count_chinese(&num_data).as_str(),
                         );
                     }
                 }
@@ -278,7 +273,7 @@ impl Percentage {
                             pre_data.as_str(),
                             Digit::new(num_data.as_str().to_string())
                                 .to_chntext()
-                                .unwrap(),
+                                .unwrap_or_default(),
                         );
                     }
                 }
@@ -335,22 +330,14 @@ impl TelePhone {
                             pre_data.as_str(),
                             format!(
                                 "零{}",
-                                pre_data
-                                    .parse::<u128>()
-                                    .unwrap()
-                                    .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                                    .as_str()
+                                naive_chinese(&pre_data).as_str()
                             )
                             .as_str(),
                         );
                     } else {
                         chntext = chntext.replace(
                             pre_data.as_str(),
-                            pre_data
-                                .parse::<u128>()
-                                .unwrap()
-                                .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                                .as_str(),
+                            naive_chinese(&pre_data).as_str(),
                         );
                     }
                 }
@@ -359,11 +346,7 @@ impl TelePhone {
                 if !tel_data.is_empty() {
                     chntext = chntext.replace(
                         tel_data.as_str(),
-                        tel_data
-                            .parse::<u128>()
-                            .unwrap()
-                            .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                            .as_str(),
+                        naive_chinese(&tel_data).as_str(),
                     );
                 }
             }
@@ -416,11 +399,7 @@ impl MobilePhone {
                 if !pre_data.is_empty() {
                     chntext = chntext.replace(
                         format!("{} ", pre_data.as_str()).as_str(),
-                        pre_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                            .as_str(),
+                        naive_chinese(&pre_data).as_str(),
                     );
                 }
             }
@@ -428,11 +407,7 @@ impl MobilePhone {
                 if !tel_data.is_empty() {
                     chntext = chntext.replace(
                         tel_data.as_str(),
-                        tel_data
-                            .parse::<u128>()
-                            .unwrap()
-                            .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                            .as_str(),
+                        naive_chinese(&tel_data).as_str(),
                     );
                 }
             }
@@ -478,11 +453,7 @@ impl Date {
                 if !year_data.is_empty() {
                     chntext = chntext.replace(
                         year_data.as_str(),
-                        year_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                            .as_str(),
+                        naive_chinese(&year_data).as_str(),
                     );
                 }
             }
@@ -490,16 +461,7 @@ impl Date {
                 if !month_data.is_empty() {
                     chntext = chntext.replace(
                         month_data.as_str(),
-                        month_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese(
-                                ChineseVariant::Simple,
-                                ChineseCase::Lower,
-                                ChineseCountMethod::TenThousand,
-                            )
-                            .unwrap()
-                            .as_str(),
+                        count_chinese(&month_data).as_str(),
                     );
                 }
             }
@@ -507,16 +469,7 @@ impl Date {
                 if !day_data.is_empty() {
                     chntext = chntext.replace(
                         day_data.as_str(),
-                        day_data
-                            .parse::<u32>()
-                            .unwrap()
-                            .to_chinese(
-                                ChineseVariant::Simple,
-                                ChineseCase::Lower,
-                                ChineseCountMethod::TenThousand,
-                            )
-                            .unwrap()
-                            .as_str(),
+                        count_chinese(&day_data).as_str(),
                     );
                 }
             }
@@ -551,7 +504,7 @@ impl Money {
                         matched.as_str(),
                         Digit::new(matched.as_str().to_string())
                             .to_chntext()
-                            .unwrap(),
+                            .unwrap_or_default(),
                     );
                 }
             }
@@ -599,7 +552,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut date = Date::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &date.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), date.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -613,7 +566,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut money = Money::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &money.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), money.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -628,7 +581,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut mobilephone = MobilePhone::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &mobilephone.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), mobilephone.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -636,7 +589,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut telphone = TelePhone::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &telphone.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), telphone.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -646,7 +599,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut fraction = Fraction::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &fraction.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), fraction.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -656,7 +609,7 @@ impl NSWNormalizer {
         for matcher in pattern.captures_iter(&text.clone()) {
             if let Some(match_str) = matcher.get(0) {
                 let mut percent = Percentage::new(match_str.as_str().to_string());
-                let replaced = text.replace(match_str.as_str(), &percent.to_chntext().unwrap());
+                let replaced = text.replace(match_str.as_str(), percent.to_chntext().unwrap_or_default());
                 text = replaced;
             }
         }
@@ -670,7 +623,7 @@ impl NSWNormalizer {
                     match_str.as_str(),
                     Digit::new(match_str.as_str().to_string())
                         .to_chntext()
-                        .unwrap(),
+                        .unwrap_or_default(),
                 );
                 text = replaced;
             }
@@ -682,12 +635,7 @@ impl NSWNormalizer {
             if let Some(match_str) = matcher.get(0) {
                 let replaced = text.replace(
                     match_str.as_str(),
-                    match_str
-                        .as_str()
-                        .parse::<u128>()
-                        .unwrap()
-                        .to_chinese_naive(ChineseVariant::Simple, ChineseCase::Lower)
-                        .as_str(),
+                    naive_chinese(match_str.as_str()).as_str(),
                 );
                 text = replaced;
             }
@@ -701,7 +649,7 @@ impl NSWNormalizer {
                     match_str.as_str(),
                     Digit::new(match_str.as_str().to_string())
                         .to_chntext()
-                        .unwrap(),
+                        .unwrap_or_default(),
                 );
                 text = replaced;
             }
@@ -749,6 +697,14 @@ mod tests {
         print!("symbols: {:?} \n", *SYMBOLS);
     }
 
+    #[test]
+    fn test_normalize_long_numbers() {
+        // 超出 u32 的数字不能导致 panic
+        assert_eq!(NSWNormalizer::new("12345678901个").normalize(), "一二三四五六七八九零一个");
+        assert_eq!(Digit::new("5.12346789012".to_string()).to_chntext(), Some("五点一二三四六七八九零一二"));
+        assert!(!NSWNormalizer::new("99999999999年3月1日").normalize().is_empty());
+    }
+
     #[test]
     fn test_normalize() {
         let texts = vec![
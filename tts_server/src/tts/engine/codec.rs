@@ -0,0 +1,67 @@
+// 压缩音频编码：无损 FLAC 与有损 Ogg Vorbis，均为 16-bit 单声道
+use super::audio::i16_to_f32;
+use super::error::{Result, TTSError};
+use flacenc::component::BitRepr;
+use flacenc::error::Verify;
+use std::num::{NonZeroU32, NonZeroU8};
+use vorbis_rs::VorbisEncoderBuilder;
+
+// libvorbis 推荐的分块大小
+const VORBIS_BLOCK_SIZE: usize = 1024;
+
+fn audio_error(e: impl std::fmt::Debug) -> TTSError {
+    TTSError::Audio(format!("{:?}", e))
+}
+
+pub fn encode_flac(samples: &[i16], sample_rate: usize) -> Result<Vec<u8>> {
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(audio_error)?;
+    let samples: Vec<i32> = samples.iter().map(|&sample| sample as i32).collect();
+    let source = flacenc::source::MemSource::from_samples(&samples, 1, 16, sample_rate);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size).map_err(audio_error)?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream.write(&mut sink).map_err(audio_error)?;
+    Ok(sink.into_inner())
+}
+
+pub fn encode_vorbis(samples: &[i16], sample_rate: usize) -> Result<Vec<u8>> {
+    let sample_rate = NonZeroU32::new(sample_rate as u32).ok_or_else(|| audio_error("sample rate must not be zero"))?;
+    let mut encoder = VorbisEncoderBuilder::new(sample_rate, NonZeroU8::MIN, Vec::new())
+        .and_then(|mut builder| builder.build())
+        .map_err(audio_error)?;
+
+    let audio = i16_to_f32(samples);
+    for block in audio.chunks(VORBIS_BLOCK_SIZE) {
+        encoder.encode_audio_block([block]).map_err(audio_error)?;
+    }
+    encoder.finish().map_err(audio_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(sample_rate: usize) -> Vec<i16> {
+        (0..sample_rate)
+            .map(|i| ((i as f32 * 440.0 * 2.0 * std::f32::consts::PI / sample_rate as f32).sin() * 8000.0) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_flac() {
+        let samples = sine(24000);
+        let flac = encode_flac(&samples, 24000).unwrap();
+        assert_eq!(&flac[0..4], b"fLaC");
+        assert!(flac.len() < samples.len() * 2);
+    }
+
+    #[test]
+    fn test_encode_vorbis() {
+        let samples = sine(16000);
+        let ogg = encode_vorbis(&samples, 16000).unwrap();
+        assert_eq!(&ogg[0..4], b"OggS");
+        assert!(ogg.len() < samples.len() * 2);
+    }
+}
@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+// 合成过程中的错误，按阶段划分：文本前端、模型（声学模型与声码器）、音频处理
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TTSError {
+    #[error("text has nothing to synthesize")]
+    EmptyText,
+    #[error("unknown symbol {symbol:?} in {text:?}")]
+    UnknownSymbol { symbol: String, text: String },
+    #[error("text frontend failed: {0}")]
+    Frontend(String),
+    #[error("failed to load model: {0}")]
+    ModelLoad(String),
+    #[error("model inference failed: {0}")]
+    Inference(String),
+    #[error("audio processing failed: {0}")]
+    Audio(String),
+}
+
+pub type Result<T, E = TTSError> = std::result::Result<T, E>;
+
+impl TTSError {
+    // 由输入文本导致的错误，重试同样的请求不会成功
+    pub fn is_input_error(&self) -> bool {
+        matches!(self, TTSError::EmptyText | TTSError::UnknownSymbol { .. } | TTSError::Frontend(_))
+    }
+}
+
+impl From<tflite::Error> for TTSError {
+    fn from(e: tflite::Error) -> Self {
+        TTSError::Inference(e.to_string())
+    }
+}
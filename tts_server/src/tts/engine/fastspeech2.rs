@@ -0,0 +1,141 @@
+use super::error::{Result, TTSError};
+use super::interpreter::{InterpreterPool, PooledInterpreter};
+use super::model::{AcousticModel, AcousticOutput, Prosody};
+use super::timing::Alignment;
+use tflite::context::ElemKindOf;
+use tflite::TensorIndex;
+
+// 梅尔谱（postnet 之后）与各 token 帧数在输出中的位置
+const MEL_OUTPUT: usize = 1;
+const DURATION_OUTPUT: usize = 2;
+
+// 模型输入的含义
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Input {
+    Ids,
+    Speaker,
+    Speed,
+    F0,
+    Energy,
+}
+
+impl Input {
+    // 按张量名识别输入，名称无法识别时按 TensorFlowTTS 导出的顺序
+    fn resolve(name: Option<&str>, position: usize) -> Option<Input> {
+        match name {
+            Some(name) if name.contains("input_ids") => Some(Input::Ids),
+            Some(name) if name.contains("speaker_ids") => Some(Input::Speaker),
+            Some(name) if name.contains("speed_ratios") => Some(Input::Speed),
+            Some(name) if name.contains("f0_ratios") => Some(Input::F0),
+            Some(name) if name.contains("energy_ratios") => Some(Input::Energy),
+            _ => [Input::Ids, Input::Speaker, Input::Speed, Input::F0, Input::Energy]
+                .get(position)
+                .copied(),
+        }
+    }
+}
+
+// TensorFlowTTS 导出的 FastSpeech2：非自回归，可控制语速、基频与能量
+pub struct FastSpeech2 {
+    pool: InterpreterPool,
+    num_speakers: Option<usize>,
+}
+
+impl FastSpeech2 {
+    pub fn new(pool: InterpreterPool) -> Result<Self> {
+        let num_speakers = pool.speaker_count()?;
+        Ok(Self { pool, num_speakers })
+    }
+
+    fn inputs(interpreter: &PooledInterpreter) -> Vec<(TensorIndex, Option<Input>)> {
+        interpreter
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| {
+                let name = interpreter.tensor_info(index).map(|info| info.name);
+                (index, Input::resolve(name.as_deref(), position))
+            })
+            .collect()
+    }
+
+    fn fill<T: ElemKindOf + Copy>(interpreter: &mut PooledInterpreter, index: TensorIndex, input: &[T]) -> Result<()> {
+        let tensor: &mut [T] = interpreter.tensor_data_mut(index)?;
+        if tensor.len() != input.len() {
+            return Err(TTSError::Inference(format!(
+                "acoustic model input {} expects {} values, got {}",
+                index,
+                tensor.len(),
+                input.len()
+            )));
+        }
+        tensor.copy_from_slice(input);
+        Ok(())
+    }
+}
+
+impl AcousticModel for FastSpeech2 {
+    fn infer(&self, input_ids: &[i32], speaker: i32, prosody: &Prosody) -> Result<AcousticOutput> {
+        self.pool.run(|interpreter| {
+            let inputs = Self::inputs(interpreter);
+            let ids_index = inputs
+                .iter()
+                .find(|(_, input)| *input == Some(Input::Ids))
+                .map(|(index, _)| *index)
+                .ok_or_else(|| TTSError::Inference("acoustic model has no input_ids input".to_string()))?;
+            interpreter.resize_input(ids_index, &[1, input_ids.len() as i32])?;
+
+            for (index, input) in &inputs {
+                match input {
+                    Some(Input::Ids) => Self::fill(interpreter, *index, input_ids)?,
+                    Some(Input::Speaker) => Self::fill(interpreter, *index, &[speaker])?,
+                    // 模型按 speed_ratios 缩放每个 token 的帧数，值越大越慢
+                    Some(Input::Speed) => Self::fill(interpreter, *index, &[1.0 / prosody.speed])?,
+                    Some(Input::F0) => Self::fill(interpreter, *index, &[prosody.pitch])?,
+                    Some(Input::Energy) => Self::fill(interpreter, *index, &[prosody.energy])?,
+                    None => continue,
+                }
+            }
+
+            interpreter.invoke()?;
+
+            let outputs = interpreter.outputs().to_vec();
+            let output_index = *outputs
+                .get(MEL_OUTPUT)
+                .ok_or_else(|| TTSError::Inference("acoustic model has no mel output".to_string()))?;
+            let mel: &[f32] = interpreter.tensor_data(output_index)?;
+
+            let alignment = outputs.get(DURATION_OUTPUT).and_then(|&index| {
+                let durations: &[i32] = interpreter.tensor_data(index).ok()?;
+                Alignment::from_durations(durations)
+            });
+
+            Ok(AcousticOutput {
+                mel: mel.to_owned(),
+                alignment,
+            })
+        })
+    }
+
+    fn num_speakers(&self) -> Option<usize> {
+        self.num_speakers
+    }
+
+    fn controls_prosody(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_input() {
+        assert_eq!(Input::resolve(Some("serving_default_f0_ratios:0"), 0), Some(Input::F0));
+        assert_eq!(Input::resolve(Some("speed_ratios"), 4), Some(Input::Speed));
+        assert_eq!(Input::resolve(None, 1), Some(Input::Speaker));
+        assert_eq!(Input::resolve(Some("unknown"), 4), Some(Input::Energy));
+        assert_eq!(Input::resolve(None, 5), None);
+    }
+}
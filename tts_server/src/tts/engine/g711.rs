@@ -0,0 +1,107 @@
+// ITU-T G.711 mu-law / A-law 编解码，算法与 Sun 的参考实现 g711.c 一致
+
+const SEG_UEND: [i32; 8] = [0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF, 0x1FFF];
+const SEG_AEND: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 8159;
+
+fn segment(value: i32, table: &[i32; 8]) -> usize {
+    table.iter().position(|&end| value <= end).unwrap_or(table.len())
+}
+
+pub fn linear_to_ulaw(sample: i16) -> u8 {
+    // 14-bit 有效精度
+    let mut pcm_val = (sample as i32) >> 2;
+    let mask = if pcm_val < 0 {
+        pcm_val = -pcm_val;
+        0x7F
+    } else {
+        0xFF
+    };
+    pcm_val = pcm_val.min(ULAW_CLIP) + (ULAW_BIAS >> 2);
+
+    let seg = segment(pcm_val, &SEG_UEND);
+    if seg >= 8 {
+        return (0x7F ^ mask) as u8;
+    }
+    let uval = ((seg as i32) << 4) | ((pcm_val >> (seg + 1)) & 0xF);
+    (uval ^ mask) as u8
+}
+
+pub fn ulaw_to_linear(u_val: u8) -> i16 {
+    let u_val = !u_val as i32;
+    let mut t = ((u_val & 0xF) << 3) + ULAW_BIAS;
+    t <<= (u_val & 0x70) >> 4;
+    (if u_val & 0x80 != 0 { ULAW_BIAS - t } else { t - ULAW_BIAS }) as i16
+}
+
+pub fn linear_to_alaw(sample: i16) -> u8 {
+    // 13-bit 有效精度
+    let mut pcm_val = (sample as i32) >> 3;
+    let mask = if pcm_val >= 0 {
+        0xD5
+    } else {
+        pcm_val = -pcm_val - 1;
+        0x55
+    };
+
+    let seg = segment(pcm_val, &SEG_AEND);
+    if seg >= 8 {
+        return (0x7F ^ mask) as u8;
+    }
+    let mut aval = (seg as i32) << 4;
+    if seg < 2 {
+        aval |= (pcm_val >> 1) & 0xF;
+    } else {
+        aval |= (pcm_val >> seg) & 0xF;
+    }
+    (aval ^ mask) as u8
+}
+
+pub fn alaw_to_linear(a_val: u8) -> i16 {
+    let a_val = (a_val ^ 0x55) as i32;
+    let mut t = (a_val & 0xF) << 4;
+    let seg = (a_val & 0x70) >> 4;
+    match seg {
+        0 => t += 8,
+        1 => t += 0x108,
+        _ => {
+            t += 0x108;
+            t <<= seg - 1;
+        }
+    }
+    (if a_val & 0x80 != 0 { t } else { -t }) as i16
+}
+
+pub fn encode_ulaw(samples: &[i16]) -> Vec<u8> {
+    samples.iter().map(|&sample| linear_to_ulaw(sample)).collect()
+}
+
+pub fn encode_alaw(samples: &[i16]) -> Vec<u8> {
+    samples.iter().map(|&sample| linear_to_alaw(sample)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_g711_silence() {
+        assert_eq!(linear_to_ulaw(0), 0xFF);
+        assert_eq!(linear_to_alaw(0), 0xD5);
+        assert_eq!(ulaw_to_linear(0xFF), 0);
+        assert_eq!(alaw_to_linear(0xD5), 8);
+    }
+
+    #[test]
+    fn test_g711_round_trip() {
+        for sample in (i16::MIN..=i16::MAX).step_by(97) {
+            // 对数量化的误差随幅度增大，约为幅度的 1/16
+            let tolerance = (sample as i32).abs() / 16 + 16;
+            let ulaw = ulaw_to_linear(linear_to_ulaw(sample)) as i32;
+            assert!((ulaw - sample as i32).abs() <= tolerance, "ulaw {} -> {}", sample, ulaw);
+            let alaw = alaw_to_linear(linear_to_alaw(sample)) as i32;
+            assert!((alaw - sample as i32).abs() <= tolerance, "alaw {} -> {}", sample, alaw);
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// 纯 Rust 的 Griffin-Lim 声码器：梅尔谱 → 线性幅度谱，再迭代估计相位。
+// 音质不如神经声码器，但不依赖模型文件，用于没有配置声码器的音色与测试中的参考
+use super::error::{Result, TTSError};
+use super::mb_melgan::NUM_MELS;
+use super::model::Vocoder;
+use super::npy;
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// 快速 Griffin-Lim 的动量
+const MOMENTUM: f32 = 0.99;
+
+// 特征提取参数需与声学模型训练时一致，默认值为 TensorFlowTTS 标贝的配置
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GriffinLimConfig {
+    pub sample_rate: usize,
+    pub fft_size: usize,
+    pub hop_size: usize,
+    pub win_length: usize,
+    pub fmin: f32,
+    pub fmax: f32,
+    /// 相位重建的迭代次数
+    pub iterations: usize,
+    /// 训练时梅尔谱归一化用的 stats.npy（均值与标准差），不填时认为输入是 log10 梅尔谱
+    pub stats: Option<String>,
+}
+
+impl Default for GriffinLimConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 24000,
+            fft_size: 2048,
+            hop_size: 300,
+            win_length: 1200,
+            fmin: 80.0,
+            fmax: 7600.0,
+            iterations: 32,
+            stats: None,
+        }
+    }
+}
+
+pub struct GriffinLim {
+    config: GriffinLimConfig,
+    // 梅尔滤波器组的伪逆，按 [频点][梅尔] 排列
+    mel_inverse: Vec<f32>,
+    // 补零到 fft_size 的 hann 窗
+    window: Vec<f32>,
+    // 反归一化用的均值与标准差
+    stats: Option<(Vec<f32>, Vec<f32>)>,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+}
+
+impl GriffinLim {
+    // config.stats 为已解析的路径
+    pub fn new(config: GriffinLimConfig) -> Result<Self> {
+        let invalid = |message: &str| TTSError::ModelLoad(format!("griffin_lim: {}", message));
+        let c = &config;
+        if c.fft_size < 2 || !c.fft_size.is_multiple_of(2) {
+            return Err(invalid("fft_size must be a positive even number"));
+        }
+        if c.hop_size == 0 || c.win_length == 0 || c.win_length > c.fft_size {
+            return Err(invalid("hop_size must be positive and win_length within 1..=fft_size"));
+        }
+        if c.sample_rate == 0 || c.fmin < 0.0 || c.fmin >= c.fmax || c.fmax > c.sample_rate as f32 / 2.0 {
+            return Err(invalid("fmin and fmax must satisfy 0 <= fmin < fmax <= sample_rate / 2"));
+        }
+        let stats = match &c.stats {
+            Some(path) => {
+                let data = std::fs::read(path).map_err(|e| invalid(&format!("{}: {}", path, e)))?;
+                let (shape, values) = npy::read(&data)?;
+                if shape != [2, NUM_MELS] {
+                    return Err(invalid(&format!("{}: expected shape [2, {}], got {:?}", path, NUM_MELS, shape)));
+                }
+                let (mean, scale) = values.split_at(NUM_MELS);
+                Some((mean.to_vec(), scale.to_vec()))
+            }
+            None => None,
+        };
+
+        let basis = mel_basis(c.sample_rate, c.fft_size, c.fmin as f64, c.fmax as f64);
+        let mel_inverse = pseudo_inverse(&basis, NUM_MELS, c.fft_size / 2 + 1)
+            .ok_or_else(|| invalid("mel filter bank is singular"))?;
+
+        // 周期 hann 窗，居中放入 fft_size 长的帧
+        let offset = (c.fft_size - c.win_length) / 2;
+        let mut window = vec![0.0; c.fft_size];
+        for i in 0..c.win_length {
+            window[offset + i] = (0.5 - 0.5 * (2.0 * PI * i as f64 / c.win_length as f64).cos()) as f32;
+        }
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        Ok(Self {
+            forward: planner.plan_fft_forward(c.fft_size),
+            inverse: planner.plan_fft_inverse(c.fft_size),
+            mel_inverse,
+            window,
+            stats,
+            config,
+        })
+    }
+
+    // 梅尔谱 → 线性幅度谱，按 [帧][频点] 排列
+    fn magnitude(&self, mel: &[f32]) -> Vec<f32> {
+        let bins = self.config.fft_size / 2 + 1;
+        let mut magnitude = Vec::with_capacity(mel.len() / NUM_MELS * bins);
+        let mut linear = [0.0; NUM_MELS];
+        for frame in mel.chunks_exact(NUM_MELS) {
+            for (m, value) in frame.iter().enumerate() {
+                let log = match &self.stats {
+                    Some((mean, scale)) => value * scale[m] + mean[m],
+                    None => *value,
+                };
+                linear[m] = 10f32.powf(log);
+            }
+            magnitude.extend(self.mel_inverse.chunks_exact(NUM_MELS).map(|row| {
+                row.iter().zip(&linear).map(|(w, m)| w * m).sum::<f32>().max(0.0)
+            }));
+        }
+        magnitude
+    }
+
+    // 中心对齐的 STFT，第 t 帧以第 t * hop_size 个采样为中心，帧外补零
+    fn stft(&self, signal: &[f32], frames: usize, spectrum: &mut [Complex<f32>]) -> Result<()> {
+        let (n, hop) = (self.config.fft_size, self.config.hop_size);
+        let mut buffer = vec![0.0; n];
+        let mut scratch = self.forward.make_scratch_vec();
+        for (t, output) in spectrum.chunks_exact_mut(n / 2 + 1).take(frames).enumerate() {
+            for (j, sample) in buffer.iter_mut().enumerate() {
+                let index = (t * hop + j).checked_sub(n / 2);
+                *sample = index.and_then(|i| signal.get(i)).map_or(0.0, |s| s * self.window[j]);
+            }
+            self.forward
+                .process_with_scratch(&mut buffer, output, &mut scratch)
+                .map_err(|e| TTSError::Audio(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    // 加窗叠加的逆 STFT，按窗平方和归一化
+    fn istft(&self, spectrum: &[Complex<f32>], signal: &mut [f32]) -> Result<()> {
+        let (n, hop) = (self.config.fft_size, self.config.hop_size);
+        let bins = n / 2 + 1;
+        let mut input = vec![Complex::default(); bins];
+        let mut buffer = vec![0.0; n];
+        let mut scratch = self.inverse.make_scratch_vec();
+        let mut window_sum = vec![0.0f32; signal.len()];
+        signal.fill(0.0);
+        for (t, frame) in spectrum.chunks_exact(bins).enumerate() {
+            input.copy_from_slice(frame);
+            // 实信号的直流与奈奎斯特频点虚部为 0
+            input[0].im = 0.0;
+            input[bins - 1].im = 0.0;
+            self.inverse
+                .process_with_scratch(&mut input, &mut buffer, &mut scratch)
+                .map_err(|e| TTSError::Audio(e.to_string()))?;
+            for (j, sample) in buffer.iter().enumerate() {
+                if let Some(i) = (t * hop + j).checked_sub(n / 2).filter(|&i| i < signal.len()) {
+                    signal[i] += sample / n as f32 * self.window[j];
+                    window_sum[i] += self.window[j] * self.window[j];
+                }
+            }
+        }
+        for (sample, sum) in signal.iter_mut().zip(window_sum) {
+            if sum > 1e-8 {
+                *sample /= sum;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Vocoder for GriffinLim {
+    fn infer(&self, mel: &[f32]) -> Result<Vec<f32>> {
+        if mel.is_empty() || !mel.len().is_multiple_of(NUM_MELS) {
+            return Err(TTSError::Inference(format!("invalid mel length {}", mel.len())));
+        }
+        let frames = mel.len() / NUM_MELS;
+        let magnitude = self.magnitude(mel);
+
+        // 固定的伪随机初始相位，保证同样的输入得到同样的波形
+        let mut seed = 0x2545_f491_u32;
+        let mut spectrum: Vec<Complex<f32>> = magnitude
+            .iter()
+            .map(|m| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                Complex::from_polar(*m, seed as f32 / u32::MAX as f32 * std::f32::consts::TAU)
+            })
+            .collect();
+        let mut signal = vec![0.0; frames * self.config.hop_size];
+        let mut rebuilt = vec![Complex::default(); spectrum.len()];
+        let mut previous = rebuilt.clone();
+        for _ in 0..self.config.iterations {
+            self.istft(&spectrum, &mut signal)?;
+            self.stft(&signal, frames, &mut rebuilt)?;
+            // 快速 Griffin-Lim：用上一轮的结果外推相位
+            for (((s, r), p), m) in spectrum.iter_mut().zip(&rebuilt).zip(&previous).zip(&magnitude) {
+                let phase = r - p * (MOMENTUM / (1.0 + MOMENTUM));
+                let norm = phase.norm();
+                *s = match norm > 1e-16 {
+                    true => phase * (m / norm),
+                    false => Complex::new(*m, 0.0),
+                };
+            }
+            std::mem::swap(&mut previous, &mut rebuilt);
+        }
+        self.istft(&spectrum, &mut signal)?;
+        Ok(signal)
+    }
+
+    fn sample_rate(&self) -> usize {
+        self.config.sample_rate
+    }
+
+    fn hop_size(&self) -> usize {
+        self.config.hop_size
+    }
+}
+
+// librosa 的 slaney 梅尔刻度
+fn hz_to_mel(hz: f64) -> f64 {
+    let log_step = 6.4f64.ln() / 27.0;
+    match hz < 1000.0 {
+        true => hz * 3.0 / 200.0,
+        false => 15.0 + (hz / 1000.0).ln() / log_step,
+    }
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    let log_step = 6.4f64.ln() / 27.0;
+    match mel < 15.0 {
+        true => mel * 200.0 / 3.0,
+        false => 1000.0 * ((mel - 15.0) * log_step).exp(),
+    }
+}
+
+// 与 librosa.filters.mel 相同的三角滤波器组（slaney 归一化），按 [梅尔][频点] 排列
+fn mel_basis(sample_rate: usize, fft_size: usize, fmin: f64, fmax: f64) -> Vec<f64> {
+    let bins = fft_size / 2 + 1;
+    let (low, high) = (hz_to_mel(fmin), hz_to_mel(fmax));
+    let points: Vec<f64> = (0..NUM_MELS + 2)
+        .map(|i| mel_to_hz(low + (high - low) * i as f64 / (NUM_MELS + 1) as f64))
+        .collect();
+    let mut basis = vec![0.0; NUM_MELS * bins];
+    for (m, row) in basis.chunks_exact_mut(bins).enumerate() {
+        let (left, center, right) = (points[m], points[m + 1], points[m + 2]);
+        let norm = 2.0 / (right - left);
+        for (k, weight) in row.iter_mut().enumerate() {
+            let hz = k as f64 * sample_rate as f64 / fft_size as f64;
+            let rising = (hz - left) / (center - left);
+            let falling = (right - hz) / (right - center);
+            *weight = rising.min(falling).max(0.0) * norm;
+        }
+    }
+    basis
+}
+
+// 滤波器组 M 的伪逆 Mᵀ(MMᵀ + λI)⁻¹，按 [频点][梅尔] 排列；λ 很小，只为避免病态
+fn pseudo_inverse(basis: &[f64], rows: usize, cols: usize) -> Option<Vec<f32>> {
+    let mut gram = vec![0.0; rows * rows];
+    for i in 0..rows {
+        for j in 0..rows {
+            gram[i * rows + j] = (0..cols).map(|k| basis[i * cols + k] * basis[j * cols + k]).sum();
+        }
+    }
+    let ridge = 1e-6 * (0..rows).map(|i| gram[i * rows + i]).sum::<f64>() / rows as f64;
+    for i in 0..rows {
+        gram[i * rows + i] += ridge;
+    }
+    let inverse = invert(gram, rows)?;
+    let mut result = vec![0.0; cols * rows];
+    for k in 0..cols {
+        for j in 0..rows {
+            result[k * rows + j] = (0..rows).map(|i| basis[i * cols + k] * inverse[i * rows + j]).sum::<f64>() as f32;
+        }
+    }
+    Some(result)
+}
+
+// 高斯-约当消元求逆，矩阵奇异时返回 None
+fn invert(mut matrix: Vec<f64>, n: usize) -> Option<Vec<f64>> {
+    let mut inverse = vec![0.0; n * n];
+    for i in 0..n {
+        inverse[i * n + i] = 1.0;
+    }
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| matrix[a * n + col].abs().total_cmp(&matrix[b * n + col].abs()))?;
+        if matrix[pivot * n + col].abs() < 1e-300 {
+            return None;
+        }
+        for k in 0..n {
+            matrix.swap(col * n + k, pivot * n + k);
+            inverse.swap(col * n + k, pivot * n + k);
+        }
+        let scale = matrix[col * n + col];
+        for k in 0..n {
+            matrix[col * n + k] /= scale;
+            inverse[col * n + k] /= scale;
+        }
+        for row in (0..n).filter(|&row| row != col) {
+            let factor = matrix[row * n + col];
+            if factor != 0.0 {
+                for k in 0..n {
+                    matrix[row * n + k] -= factor * matrix[col * n + k];
+                    inverse[row * n + k] -= factor * inverse[col * n + k];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按 TensorFlowTTS 的预处理计算 log10 梅尔谱
+    fn log_mel(vocoder: &GriffinLim, audio: &[f32], frames: usize) -> Vec<f32> {
+        let c = &vocoder.config;
+        let bins = c.fft_size / 2 + 1;
+        let basis = mel_basis(c.sample_rate, c.fft_size, c.fmin as f64, c.fmax as f64);
+        let mut spectrum = vec![Complex::default(); frames * bins];
+        vocoder.stft(audio, frames, &mut spectrum).unwrap();
+        spectrum
+            .chunks_exact(bins)
+            .flat_map(|frame| {
+                basis.chunks_exact(bins).map(move |row| {
+                    let energy: f64 = row.iter().zip(frame).map(|(w, s)| w * s.norm() as f64).sum();
+                    energy.max(1e-10).log10() as f32
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_griffin_lim() {
+        let vocoder = GriffinLim::new(GriffinLimConfig::default()).unwrap();
+        let (sample_rate, frames) = (24000, 80);
+        let audio: Vec<f32> = (0..frames * 300)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        let mel = log_mel(&vocoder, &audio, frames);
+
+        let output = vocoder.infer(&mel).unwrap();
+        assert_eq!(output.len(), audio.len());
+        assert_eq!(vocoder.infer(&mel).unwrap(), output);
+
+        // 去掉首尾后按过零次数估计频率
+        let middle = &output[4800..output.len() - 4800];
+        let crossings = middle.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        let frequency = crossings as f32 / 2.0 / (middle.len() as f32 / sample_rate as f32);
+        assert!((frequency - 440.0).abs() < 20.0, "{}", frequency);
+        let peak = middle.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.2 && peak < 1.0, "{}", peak);
+
+        assert!(vocoder.infer(&mel[1..]).is_err());
+        let config = GriffinLimConfig { fmax: 16000.0, ..Default::default() };
+        assert!(GriffinLim::new(config).is_err());
+    }
+}
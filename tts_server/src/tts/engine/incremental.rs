@@ -0,0 +1,74 @@
+use super::splitter;
+
+// 增量文本分句器：缓存逐步到达的文本片段（如大模型的 token 流），
+// 遇到分句标点时立即输出完整的分句
+#[derive(Debug, Default)]
+pub struct IncrementalSplitter {
+    buffer: String,
+}
+
+impl IncrementalSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 追加文本片段，返回已经完整的分句（不含分隔标点）
+    pub fn push(&mut self, fragment: &str) -> Vec<String> {
+        let mut clauses = Vec::new();
+        for c in fragment.chars() {
+            if splitter::punctuation(c).is_some() {
+                if let Some(clause) = self.take() {
+                    clauses.push(clause);
+                }
+            } else {
+                self.buffer.push(c);
+            }
+        }
+        clauses
+    }
+
+    // 输入结束，返回剩余的文本
+    pub fn finish(&mut self) -> Option<String> {
+        self.take()
+    }
+
+    pub fn pending(&self) -> &str {
+        &self.buffer
+    }
+
+    fn take(&mut self) -> Option<String> {
+        let clause = std::mem::take(&mut self.buffer);
+        if clause.trim().is_empty() {
+            None
+        } else {
+            Some(clause.trim().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_split() {
+        let mut splitter = IncrementalSplitter::new();
+        let mut clauses = Vec::new();
+        for fragment in ["今天天", "气不错", "，有50%的", "概率会下雨！", "！明", "天见"] {
+            clauses.extend(splitter.push(fragment));
+        }
+        assert_eq!(clauses, vec!["今天天气不错", "有50%的概率会下雨"]);
+        assert_eq!(splitter.pending(), "明天见");
+        assert_eq!(splitter.finish(), Some("明天见".to_string()));
+        assert_eq!(splitter.finish(), None);
+    }
+
+    #[test]
+    fn test_incremental_english() {
+        let mut splitter = IncrementalSplitter::new();
+        assert!(splitter.push("Hello").is_empty());
+        assert_eq!(splitter.push(" world, how"), vec!["Hello world"]);
+        assert_eq!(splitter.push(" are you?"), vec!["how are you"]);
+        assert_eq!(splitter.finish(), None);
+    }
+}
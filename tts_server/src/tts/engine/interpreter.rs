@@ -0,0 +1,104 @@
+use super::error::{Result, TTSError};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use tflite::ops::builtin::BuiltinOpResolver;
+use tflite::{FlatBufferModel, Interpreter, InterpreterBuilder, TensorIndex};
+
+// 解释器持有自己的模型副本，不借用引擎，可以在线程间传递、长期复用
+type ModelInterpreter = Interpreter<'static, BuiltinOpResolver>;
+
+// 池中的解释器，记录各输入张量当前的形状
+pub struct PooledInterpreter {
+    interpreter: ModelInterpreter,
+    shapes: HashMap<TensorIndex, Vec<TensorIndex>>,
+}
+
+impl PooledInterpreter {
+    // 形状与上次相同时跳过 set_shapes 与 allocate_tensors
+    pub fn resize_input(&mut self, index: TensorIndex, shape: &[TensorIndex]) -> Result<()> {
+        if self.shapes.get(&index).is_some_and(|current| current == shape) {
+            return Ok(());
+        }
+        // 调整失败时张量状态未知，下次必须重新分配
+        self.shapes.remove(&index);
+        self.interpreter.set_shapes(index, shape)?;
+        self.interpreter.allocate_tensors()?;
+        self.shapes.insert(index, shape.to_vec());
+        Ok(())
+    }
+}
+
+impl Deref for PooledInterpreter {
+    type Target = ModelInterpreter;
+
+    fn deref(&self) -> &Self::Target {
+        &self.interpreter
+    }
+}
+
+impl DerefMut for PooledInterpreter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.interpreter
+    }
+}
+
+// 单个模型的解释器池：启动时预先构建，请求之间复用；
+// 并发超过池大小时临时构建新的解释器，用完后同样归还
+pub struct InterpreterPool {
+    name: String,
+    model_buffer: Vec<u8>,
+    threads: i32,
+    idle: Mutex<Vec<PooledInterpreter>>,
+}
+
+impl InterpreterPool {
+    pub fn new(path: &str, size: usize, threads: i32) -> Result<Self> {
+        let model_buffer = std::fs::read(path).map_err(|e| TTSError::ModelLoad(format!("{}: {}", path, e)))?;
+        let pool = Self {
+            name: path.to_string(),
+            model_buffer,
+            threads,
+            idle: Mutex::new(Vec::new()),
+        };
+        let interpreters = (0..size).map(|_| pool.build()).collect::<Result<Vec<_>>>()?;
+        *pool.idle.lock().unwrap() = interpreters;
+        Ok(pool)
+    }
+
+    fn build(&self) -> Result<PooledInterpreter> {
+        let load_error = |e: tflite::Error| TTSError::ModelLoad(format!("{}: {}", self.name, e));
+        let model = FlatBufferModel::build_from_buffer(self.model_buffer.clone()).map_err(load_error)?;
+        let interpreter = InterpreterBuilder::new(model, BuiltinOpResolver::default())
+            .and_then(|builder| builder.build_with_threads(self.threads))
+            .map_err(load_error)?;
+        Ok(PooledInterpreter { interpreter, shapes: HashMap::new() })
+    }
+
+    // 模型的说话人嵌入表大小，按 TensorFlowTTS 的 speaker_embeddings 层查找，没有时为 None
+    pub fn speaker_count(&self) -> Result<Option<usize>> {
+        self.run(|interpreter| {
+            Ok((0..interpreter.tensors_size() as TensorIndex)
+                .filter_map(|index| interpreter.tensor_info(index))
+                .find(|info| info.name.contains("speaker_embeddings") && info.dims.len() == 2)
+                .map(|info| info.dims[0]))
+        })
+    }
+
+    // 取出一个解释器执行 f；执行出错或 panic 的解释器状态未知，直接丢弃不再归还
+    pub fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut PooledInterpreter) -> Result<T>,
+    {
+        let idle = self.idle.lock().unwrap().pop();
+        let mut interpreter = match idle {
+            Some(interpreter) => interpreter,
+            None => self.build()?,
+        };
+        let result = f(&mut interpreter);
+        if result.is_ok() {
+            self.idle.lock().unwrap().push(interpreter);
+        }
+        result
+    }
+}
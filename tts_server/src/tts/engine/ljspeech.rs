@@ -1,9 +1,11 @@
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::io::{Error, Read};
+use std::io::{Error, ErrorKind, Read};
 use regex::Regex;
 use lazy_static::lazy_static;
 use std::fs::File;
+use super::model::TextProcessor;
+use super::timing::TextUnit;
 
 const ABBREVIATIONS: [(&str, &str); 18] = [
     ("\\b(mrs)\\.", "misess"),
@@ -134,7 +136,8 @@ fn number_to_words_decimal(mut n: f64, precision: usize) -> String {
 
     while n > 0.0 && prec > 0 {
         n *= 10.0;
-        let digit = n.floor() as i64;
+        // 舍入后可能进位到 1.0，避免越界
+        let digit = (n.floor() as i64).clamp(0, 9);
         result += ones[digit as usize];
         n -= digit as f64;
         prec -= 1;
@@ -176,20 +179,27 @@ pub struct LJSpeechProcessor {
 // Define the implementation block for BakerProcessor
 impl LJSpeechProcessor {
     // Define the __post_init__ method
-    pub fn new() -> Result<Self, Error>{
+    pub fn new() -> Result<Self, Error> {
+        Self::with_mapper("assets/ljspeech_mapper.json")
+    }
+
+    // 从指定的 mapper 文件加载音素表与说话人
+    pub fn with_mapper(mapper_path: &str) -> Result<Self, Error> {
         // Implement the method
         let mut processor = Self {
             cleaner_names: Some(String::new()),
             symbols: Vec::new(),
             speakers_map: HashMap::new(),
-            loaded_mapper_path: Some("assets/ljspeech_mapper.json".to_string()),
+            loaded_mapper_path: Some(mapper_path.to_string()),
             symbol_to_id: HashMap::new(),
             id_to_symbol: HashMap::new(),
             processor_name: None,
             eos_id: 0
         };
 
-        processor.load_mapper().unwrap();
+        processor
+            .load_mapper()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", mapper_path, e)))?;
         if !processor.setup_eos_token().is_empty() {
             processor.add_symbol(processor.setup_eos_token());
             processor.eos_id = processor.symbol_to_id[&processor.setup_eos_token()];
@@ -229,6 +239,43 @@ impl LJSpeechProcessor {
         sequence
     }
     
+    // 与 text_to_sequence 相同，同时返回每个英文单词对应的字符区间
+    pub fn text_to_units(&self, text: &str) -> (Vec<i32>, Vec<TextUnit>) {
+        let sequence = self.text_to_sequence(text, true);
+
+        let mut units: Vec<TextUnit> = Vec::new();
+        let mut current: Option<TextUnit> = None;
+        for (i, &id) in sequence.iter().enumerate() {
+            let symbol = match self.id_to_symbol.get(&(id as usize)) {
+                Some(symbol) if id as usize != self.eos_id => symbol.as_str(),
+                _ => "",
+            };
+            // ARPAbet 音素以 @ 开头，单词内以空格分隔
+            let arpabet = symbol.strip_prefix('@');
+            let in_word = arpabet.is_some()
+                || (!symbol.is_empty() && symbol.chars().all(|c| c.is_alphanumeric() || c == '\''));
+            match (in_word, current.as_mut()) {
+                (true, Some(unit)) => {
+                    if arpabet.is_some() {
+                        unit.text.push(' ');
+                    }
+                    unit.text.push_str(arpabet.unwrap_or(symbol));
+                    unit.tokens.end = i + 1;
+                }
+                (true, None) => {
+                    current = Some(TextUnit {
+                        text: arpabet.unwrap_or(symbol).to_string(),
+                        tokens: i..i + 1,
+                    });
+                }
+                (false, _) => units.extend(current.take()),
+            }
+        }
+        units.extend(current);
+
+        (sequence, units)
+    }
+
     fn clean_text(&self, text: &str, _cleaner_names: &Option<String>) -> String {
         let text = expand_abbreviations(&text.to_lowercase());
         let text = expand_numbers(&text);
@@ -281,12 +328,12 @@ impl LJSpeechProcessor {
             let id_to_symbol_map: HashMap<String, String> = serde_json::from_value(id_to_symbol.clone())?;
             self.id_to_symbol = id_to_symbol_map
                 .iter()
-                .map(|(k, v)| (k.parse::<usize>().unwrap(), v.clone()))
-                .collect();
+                .map(|(k, v)| Ok((k.parse::<usize>()?, v.clone())))
+                .collect::<Result<_, std::num::ParseIntError>>()?;
         }
 
         if let Some(processor_name) = parsed_data.get("processor_name") {
-            self.processor_name = Some(processor_name.as_str().unwrap().to_string());
+            self.processor_name = processor_name.as_str().map(String::from);
         }
 
         Ok(())
@@ -294,6 +341,16 @@ impl LJSpeechProcessor {
 }
 
 
+impl TextProcessor for LJSpeechProcessor {
+    fn text_to_units(&self, text: &str) -> super::error::Result<(Vec<i32>, Vec<TextUnit>)> {
+        Ok(LJSpeechProcessor::text_to_units(self, text))
+    }
+
+    fn speakers(&self) -> &HashMap<String, usize> {
+        &self.speakers_map
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,4 +381,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_text_to_units() {
+        let ljspeech = LJSpeechProcessor::new().unwrap();
+        let (sequence, units) = ljspeech.text_to_units("What day, today?");
+        assert_eq!(sequence, ljspeech.text_to_sequence("What day, today?", true));
+        let words: Vec<&str> = units.iter().map(|unit| unit.text.as_str()).collect();
+        assert_eq!(words, vec!["what", "day", "today"]);
+        assert_eq!(units[0].tokens, 0..4);
+    }
+
 }
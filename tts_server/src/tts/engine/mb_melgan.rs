@@ -0,0 +1,64 @@
+use super::error::{Result, TTSError};
+use super::interpreter::InterpreterPool;
+use super::model::Vocoder;
+
+// 每帧梅尔谱的维数
+pub const NUM_MELS: usize = 80;
+
+// TensorFlowTTS 导出的 Multi-band MelGAN 声码器
+pub struct MbMelGan {
+    pool: InterpreterPool,
+    sample_rate: usize,
+    hop_size: usize,
+}
+
+impl MbMelGan {
+    pub fn new(pool: InterpreterPool, sample_rate: usize, hop_size: usize) -> Self {
+        Self {
+            pool,
+            sample_rate,
+            hop_size,
+        }
+    }
+}
+
+impl Vocoder for MbMelGan {
+    fn infer(&self, mel: &[f32]) -> Result<Vec<f32>> {
+        if mel.is_empty() || !mel.len().is_multiple_of(NUM_MELS) {
+            return Err(TTSError::Inference(format!("invalid mel length {}", mel.len())));
+        }
+        self.pool.run(|interpreter| {
+            let input_index = *interpreter
+                .inputs()
+                .first()
+                .ok_or_else(|| TTSError::Inference("vocoder has no input".to_string()))?;
+            interpreter.resize_input(input_index, &[1, (mel.len() / NUM_MELS) as i32, NUM_MELS as i32])?;
+
+            let tensor: &mut [f32] = interpreter.tensor_data_mut(input_index)?;
+            if tensor.len() != mel.len() {
+                return Err(TTSError::Inference(format!(
+                    "vocoder expects {} mel values, got {}",
+                    tensor.len(),
+                    mel.len()
+                )));
+            }
+            tensor.copy_from_slice(mel);
+            interpreter.invoke()?;
+
+            let output_index = *interpreter
+                .outputs()
+                .first()
+                .ok_or_else(|| TTSError::Inference("vocoder has no output".to_string()))?;
+            let output: &[f32] = interpreter.tensor_data(output_index)?;
+            Ok(output.to_owned())
+        })
+    }
+
+    fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+}
@@ -0,0 +1,73 @@
+// 声学模型输出的梅尔谱，供外部声码器使用
+use super::mb_melgan::NUM_MELS;
+use super::npy;
+use super::tts_engine::TextType;
+use serde::{Deserialize, Serialize};
+
+// 梅尔谱的导出格式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MelFormat {
+    /// NumPy .npy 文件，形状为 (frames, 80) 的 float32 数组
+    #[default]
+    Npy,
+    /// 按帧排列的小端 float32，不含头部
+    Raw,
+}
+
+// 一个分句的梅尔谱
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClauseMel {
+    pub text: String,
+    pub lang: TextType,
+    /// 合成所用的音色
+    pub voice: String,
+    /// 输入声学模型的音素 id
+    pub input_ids: Vec<i32>,
+    /// 按帧排列，每帧 NUM_MELS 个值
+    pub mel: Vec<f32>,
+    /// 音色声码器的原生采样率（Hz）
+    pub sample_rate: usize,
+    /// 每帧对应的采样数
+    pub hop_size: usize,
+}
+
+impl ClauseMel {
+    pub fn frames(&self) -> usize {
+        self.mel.len() / NUM_MELS
+    }
+
+    pub fn shape(&self) -> [usize; 2] {
+        [self.frames(), NUM_MELS]
+    }
+
+    pub fn encode(&self, format: MelFormat) -> Vec<u8> {
+        match format {
+            MelFormat::Npy => npy::write(&self.shape(), &self.mel),
+            MelFormat::Raw => self.mel.iter().flat_map(|value| value.to_le_bytes()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_mel() {
+        let mel = ClauseMel {
+            text: "你好".to_string(),
+            lang: TextType::Chinese,
+            voice: "baker".to_string(),
+            input_ids: vec![1, 2, 3],
+            mel: (0..NUM_MELS * 3).map(|i| i as f32).collect(),
+            sample_rate: 24000,
+            hop_size: 300,
+        };
+        assert_eq!(mel.shape(), [3, NUM_MELS]);
+        let raw = mel.encode(MelFormat::Raw);
+        assert_eq!(raw.len(), NUM_MELS * 3 * 4);
+        assert_eq!(raw[4..8], 1.0f32.to_le_bytes());
+        assert_eq!(npy::read(&mel.encode(MelFormat::Npy)).unwrap(), (vec![3, NUM_MELS], mel.mel.clone()));
+    }
+}
@@ -1,4 +1,24 @@
+pub mod audio;
 pub mod baker;
+pub mod bundle;
+pub mod chunk;
 pub mod cn_tn;
+pub mod codec;
+pub mod error;
+pub mod fastspeech2;
+pub mod g711;
+pub mod griffin_lim;
+pub mod incremental;
+pub mod interpreter;
 pub mod ljspeech;
-pub mod tts_engine;
\ No newline at end of file
+pub mod mb_melgan;
+pub mod mel;
+pub mod model;
+pub mod npy;
+pub mod quality;
+pub mod splitter;
+pub mod ssml;
+pub mod tacotron2;
+pub mod timing;
+pub mod tts_engine;
+pub mod voice;
\ No newline at end of file
@@ -0,0 +1,76 @@
+use super::error::Result;
+use std::collections::HashMap;
+use super::timing::{Alignment, TextUnit};
+
+// 文本前端：把一个分句转换为模型的输入序列，并给出每个字/词对应的 token 区间
+pub trait TextProcessor: Send + Sync {
+    fn text_to_units(&self, text: &str) -> Result<(Vec<i32>, Vec<TextUnit>)>;
+
+    // mapper 中的说话人名称与说话人 id
+    fn speakers(&self) -> &HashMap<String, usize>;
+}
+
+// 声学模型的输出
+pub struct AcousticOutput {
+    /// 按帧排列的梅尔谱，每帧 num_mels 个值
+    pub mel: Vec<f32>,
+    /// 输入 token 与解码步的对齐，模型不输出时为 None
+    pub alignment: Option<Alignment>,
+}
+
+// 韵律控制，均为相对于模型默认值的倍率
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    /// 语速，大于 1 时更快
+    pub speed: f32,
+    /// 基频（音高）
+    pub pitch: f32,
+    /// 能量（响度）
+    pub energy: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch: 1.0,
+            energy: 1.0,
+        }
+    }
+}
+
+// 声学模型：输入序列 → 梅尔谱
+pub trait AcousticModel: Send + Sync {
+    fn infer(&self, input_ids: &[i32], speaker: i32, prosody: &Prosody) -> Result<AcousticOutput>;
+
+    // 说话人嵌入表的大小，模型没有说话人嵌入（单说话人）时为 None
+    fn num_speakers(&self) -> Option<usize> {
+        None
+    }
+
+    // 模型能否直接控制韵律；不能时忽略音高与能量，语速由引擎对波形做时间伸缩
+    fn controls_prosody(&self) -> bool {
+        false
+    }
+
+    // 自回归解码（如 Tacotron2）可能因注意力失败而重复、漏读或提前停止，合成后需要检查
+    fn autoregressive(&self) -> bool {
+        false
+    }
+
+    // 合成结果末尾需要截掉的采样数（按引擎输出采样率计算）
+    fn tail_samples(&self) -> usize {
+        0
+    }
+}
+
+// 声码器：梅尔谱 → 波形
+pub trait Vocoder: Send + Sync {
+    fn infer(&self, mel: &[f32]) -> Result<Vec<f32>>;
+
+    // 输出波形的采样率
+    fn sample_rate(&self) -> usize;
+
+    // 每帧梅尔谱对应的输出采样数
+    fn hop_size(&self) -> usize;
+}
@@ -0,0 +1,109 @@
+// NumPy .npy 文件的读写，只支持小端 float32/float64 的 C 顺序数组
+use super::error::{Result, TTSError};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+// 返回数组的形状与按 C 顺序排列的数据
+pub fn read(data: &[u8]) -> Result<(Vec<usize>, Vec<f32>)> {
+    let invalid = |message: &str| TTSError::ModelLoad(format!("invalid npy: {}", message));
+    if data.len() < 10 || &data[..6] != MAGIC {
+        return Err(invalid("bad magic"));
+    }
+    // 1.0 版本的头长度为 2 字节，2.0 及以上为 4 字节
+    let (header_len, offset) = match data[6] {
+        1 => (u16::from_le_bytes([data[8], data[9]]) as usize, 10),
+        _ if data.len() >= 12 => (u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize, 12),
+        _ => return Err(invalid("truncated header")),
+    };
+    let header = data
+        .get(offset..offset + header_len)
+        .and_then(|header| std::str::from_utf8(header).ok())
+        .ok_or_else(|| invalid("truncated header"))?;
+    if header.contains("'fortran_order': True") {
+        return Err(invalid("fortran order is not supported"));
+    }
+    let value = |key: &str| header.split(key).nth(1).map(|rest| rest.trim_start_matches([':', ' ']));
+    let descr = value("'descr'")
+        .and_then(|rest| rest.strip_prefix('\'')?.split('\'').next())
+        .ok_or_else(|| invalid("missing descr"))?;
+    let shape: Vec<usize> = value("'shape'")
+        .and_then(|rest| rest.strip_prefix('(')?.split(')').next())
+        .ok_or_else(|| invalid("missing shape"))?
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().map_err(|_| invalid("bad shape")))
+        .collect::<Result<_>>()?;
+
+    let body = &data[offset + header_len..];
+    let values: Vec<f32> = match descr {
+        "<f4" => body.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+        "<f8" => body
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+            .collect(),
+        _ => return Err(invalid(&format!("unsupported dtype {}", descr))),
+    };
+    if values.len() != shape.iter().product::<usize>() {
+        return Err(invalid("data does not match shape"));
+    }
+    Ok((shape, values))
+}
+
+// 按 1.0 版本格式写出 float32 数组，头部补齐到 64 字节对齐
+pub fn write(shape: &[usize], values: &[f32]) -> Vec<u8> {
+    let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    let dims = match dims.len() {
+        1 => format!("{},", dims[0]),
+        _ => dims.join(", "),
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({}), }}", dims).into_bytes();
+    let padding = 64 - (MAGIC.len() + 4 + header.len() + 1) % 64;
+    header.extend(std::iter::repeat_n(b' ', padding % 64));
+    header.push(b'\n');
+
+    let mut data = Vec::with_capacity(MAGIC.len() + 4 + header.len() + values.len() * 4);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&[1, 0]);
+    data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    data.extend_from_slice(&header);
+    for value in values {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_npy() {
+        // np.save(f, np.array([[1, 2], [3, 4]], dtype=np.float64))
+        let mut header = b"{'descr': '<f8', 'fortran_order': False, 'shape': (2, 2), }".to_vec();
+        while !(10 + header.len() + 1).is_multiple_of(64) {
+            header.push(b' ');
+        }
+        header.push(b'\n');
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&[1, 0]);
+        data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        data.extend_from_slice(&header);
+        for value in [1.0f64, 2.0, 3.0, 4.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(read(&data).unwrap(), (vec![2, 2], vec![1.0, 2.0, 3.0, 4.0]));
+        assert!(read(&data[..data.len() - 8]).is_err());
+        assert!(read(b"not a npy file").is_err());
+    }
+
+    #[test]
+    fn test_write_npy() {
+        let values = [0.5, -1.0, 2.0, 3.5, 0.0, 1.0];
+        let data = write(&[3, 2], &values);
+        assert_eq!(data.len() % 4, 0);
+        assert_eq!((data.len() - values.len() * 4) % 64, 0);
+        assert_eq!(read(&data).unwrap(), (vec![3, 2], values.to_vec()));
+        assert_eq!(read(&write(&[2], &[1.0, 2.0])).unwrap(), (vec![2], vec![1.0, 2.0]));
+    }
+}
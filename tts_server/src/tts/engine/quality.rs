@@ -0,0 +1,130 @@
+// 自回归模型解码失败的检测：输出长度与输入长度明显不成比例（重复或提前停止），
+// 或音频大部分是静音或噪声
+use serde::{Deserialize, Serialize};
+
+// 每个输入 token 对应的梅尔帧数的合理范围
+const MIN_FRAMES_PER_TOKEN: f32 = 1.0;
+const MAX_FRAMES_PER_TOKEN: f32 = 20.0;
+// 按 20ms 的窗统计音量与过零率
+const WINDOW_SEC: f32 = 0.02;
+// 均方根低于该值的窗视为静音（约 -40dBFS）
+const SILENCE_RMS: f32 = 0.01;
+const MAX_SILENT_RATIO: f32 = 0.8;
+// 过零率高于该值的窗视为噪声，清辅音也较高，因此只看非静音窗中的占比
+const NOISE_ZCR: f32 = 0.35;
+const MAX_NOISY_RATIO: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeIssue {
+    /// 输出相对输入过短，通常是提前停止
+    TooShort,
+    /// 输出相对输入过长，通常是重复或没有停止
+    TooLong,
+    /// 音频大部分是静音
+    Silent,
+    /// 音频大部分是噪声
+    Noisy,
+}
+
+impl DecodeIssue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecodeIssue::TooShort => "too_short",
+            DecodeIssue::TooLong => "too_long",
+            DecodeIssue::Silent => "silent",
+            DecodeIssue::Noisy => "noisy",
+        }
+    }
+}
+
+// 重试拆分后仍未通过检测的片段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct DecodeWarning {
+    /// 所属分句的序号，从 0 开始
+    pub clause: usize,
+    /// 未通过检测的文本
+    pub text: String,
+    pub issue: DecodeIssue,
+}
+
+// 响应头中的警告列表，形如 "0:too_long, 3:silent"
+pub fn format_warnings(warnings: &[DecodeWarning]) -> String {
+    let warnings: Vec<String> = warnings
+        .iter()
+        .map(|warning| format!("{}:{}", warning.clause, warning.issue.as_str()))
+        .collect();
+    warnings.join(", ")
+}
+
+// 检查一次解码的结果，tokens 为输入序列长度，frames 为梅尔谱帧数
+pub fn check_decode(tokens: usize, frames: usize, audio: &[f32], sample_rate: usize) -> Option<DecodeIssue> {
+    let frames_per_token = frames as f32 / tokens.max(1) as f32;
+    if frames_per_token < MIN_FRAMES_PER_TOKEN {
+        return Some(DecodeIssue::TooShort);
+    }
+    if frames_per_token > MAX_FRAMES_PER_TOKEN {
+        return Some(DecodeIssue::TooLong);
+    }
+
+    let window = ((sample_rate as f32 * WINDOW_SEC) as usize).max(1);
+    let (mut windows, mut silent, mut noisy) = (0, 0, 0);
+    for samples in audio.chunks_exact(window) {
+        windows += 1;
+        let rms = (samples.iter().map(|s| s * s).sum::<f32>() / window as f32).sqrt();
+        if rms < SILENCE_RMS {
+            silent += 1;
+            continue;
+        }
+        let crossings = samples.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        if crossings as f32 / window as f32 > NOISE_ZCR {
+            noisy += 1;
+        }
+    }
+    if windows == 0 || silent as f32 > windows as f32 * MAX_SILENT_RATIO {
+        return Some(DecodeIssue::Silent);
+    }
+    if noisy as f32 > (windows - silent) as f32 * MAX_NOISY_RATIO {
+        return Some(DecodeIssue::Noisy);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_decode() {
+        let sample_rate = 24000;
+        let tone: Vec<f32> = (0..sample_rate)
+            .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 200.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        assert_eq!(check_decode(20, 80, &tone, sample_rate), None);
+        assert_eq!(check_decode(20, 10, &tone, sample_rate), Some(DecodeIssue::TooShort));
+        assert_eq!(check_decode(20, 1000, &tone, sample_rate), Some(DecodeIssue::TooLong));
+
+        let mut quiet = vec![0.0; sample_rate];
+        quiet[..2400].copy_from_slice(&tone[..2400]);
+        assert_eq!(check_decode(20, 80, &quiet, sample_rate), Some(DecodeIssue::Silent));
+        assert_eq!(check_decode(20, 80, &[], sample_rate), Some(DecodeIssue::Silent));
+
+        // 伪随机白噪声
+        let mut seed = 1u32;
+        let noise: Vec<f32> = (0..sample_rate)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed as f32 / u32::MAX as f32 - 0.5) * 0.6
+            })
+            .collect();
+        assert_eq!(check_decode(20, 80, &noise, sample_rate), Some(DecodeIssue::Noisy));
+
+        let warning = |clause, issue| DecodeWarning { clause, text: String::new(), issue };
+        let warnings = [warning(0, DecodeIssue::TooLong), warning(3, DecodeIssue::Silent)];
+        assert_eq!(format_warnings(&warnings), "0:too_long, 3:silent");
+        assert_eq!(
+            serde_json::to_value(DecodeIssue::TooShort).unwrap(),
+            DecodeIssue::TooShort.as_str()
+        );
+    }
+}
@@ -0,0 +1,258 @@
+// 按标点与换行分句，并保留分句边界的类型：逗号级、句子级与段落级的停顿时长不同，
+// 问句与感叹句单独标记，供后续的韵律处理使用
+use serde::{Deserialize, Serialize};
+
+// 分句之后的边界，按停顿由短到长排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Boundary {
+    /// 逗号、顿号、分号、冒号、破折号与括号
+    #[default]
+    Comma,
+    /// 句号、问号、叹号、省略号与单个换行
+    Sentence,
+    /// 空行分隔的段落
+    Paragraph,
+}
+
+// 分句的语气，由句末标点决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mood {
+    #[default]
+    Statement,
+    Question,
+    Exclamation,
+}
+
+// 各级边界之后的停顿（秒）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Pauses {
+    pub comma: f32,
+    pub sentence: f32,
+    pub paragraph: f32,
+}
+
+impl Default for Pauses {
+    fn default() -> Self {
+        Self {
+            comma: 0.2,
+            sentence: 0.4,
+            paragraph: 0.8,
+        }
+    }
+}
+
+impl Pauses {
+    pub fn after(&self, boundary: Boundary) -> f32 {
+        match boundary {
+            Boundary::Comma => self.comma,
+            Boundary::Sentence => self.sentence,
+            Boundary::Paragraph => self.paragraph,
+        }
+    }
+
+    pub fn is_valid(&self, max: f32) -> bool {
+        [self.comma, self.sentence, self.paragraph].iter().all(|pause| (0.0..=max).contains(pause))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piece {
+    pub text: String,
+    /// 分句之后的边界
+    pub boundary: Boundary,
+    pub mood: Mood,
+}
+
+// 不影响分句、朗读时去掉的引号与书名号；英文的撇号 ' 保留
+const DROPPED: &str = "\"“”‘’「」『』《》〈〉";
+const OPENING_BRACKETS: &str = "（(【[〔";
+const CLOSING_BRACKETS: &str = "）)】]〕";
+// 句点后不断句的英文缩写
+const ABBREVIATIONS: &[&str] = &["mr", "mrs", "ms", "dr", "st", "jr", "sr", "vs", "etc", "prof", "no", "e.g", "i.e"];
+
+// 单个标点对应的边界与语气，不含需要结合上下文判断的英文句点
+pub fn punctuation(c: char) -> Option<(Boundary, Mood)> {
+    match c {
+        '，' | ',' | '、' | '：' | ':' | '；' | ';' => Some((Boundary::Comma, Mood::Statement)),
+        '。' => Some((Boundary::Sentence, Mood::Statement)),
+        // 省略号表示停顿较长的未完句
+        '…' => Some((Boundary::Sentence, Mood::Statement)),
+        '？' | '?' => Some((Boundary::Sentence, Mood::Question)),
+        '！' | '!' => Some((Boundary::Sentence, Mood::Exclamation)),
+        _ => None,
+    }
+}
+
+struct Splitter {
+    pieces: Vec<Piece>,
+    text: String,
+}
+
+impl Splitter {
+    // 结束当前分句；没有文本时（如连续的标点）把边界与语气合并到上一个分句
+    fn end(&mut self, boundary: Boundary, mood: Mood) {
+        let text = self.text.trim().to_string();
+        self.text.clear();
+        if !text.is_empty() {
+            self.pieces.push(Piece { text, boundary, mood });
+        } else if let Some(last) = self.pieces.last_mut() {
+            last.boundary = last.boundary.max(boundary);
+            if last.mood == Mood::Statement {
+                last.mood = mood;
+            }
+        }
+    }
+
+    // 当前分句的最后一个词，用于判断缩写
+    fn last_word(&self) -> String {
+        let word = self.text.rsplit(|c: char| c.is_whitespace()).next().unwrap_or("");
+        word.to_ascii_lowercase()
+    }
+}
+
+pub fn split(text: &str) -> Vec<Piece> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut splitter = Splitter {
+        pieces: Vec::new(),
+        text: String::new(),
+    };
+    let digit_at = |i: Option<usize>| i.and_then(|i| chars.get(i)).is_some_and(|c| c.is_ascii_digit());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // 与 c 相同的连续字符数
+        let run = chars[i..].iter().take_while(|&&next| next == c).count();
+        let between_digits = digit_at(i.checked_sub(1)) && digit_at(Some(i + 1));
+        match c {
+            // 数字中的千分位、时间与小数
+            ',' | ':' | '.' if between_digits => splitter.text.push(c),
+            // 英文单词中的撇号
+            '’' if i > 0 && chars[i - 1].is_alphabetic() && chars.get(i + 1).is_some_and(|c| c.is_alphabetic()) => {
+                splitter.text.push('\'')
+            }
+            _ if DROPPED.contains(c) => {}
+            _ if OPENING_BRACKETS.contains(c) || CLOSING_BRACKETS.contains(c) => {
+                splitter.end(Boundary::Comma, Mood::Statement)
+            }
+            '.' if run >= 3 => {
+                splitter.end(Boundary::Sentence, Mood::Statement);
+                i += run;
+                continue;
+            }
+            '.' => {
+                let word = splitter.last_word();
+                let next_is_space = chars.get(i + 1).is_none_or(|c| !c.is_ascii_alphanumeric());
+                if next_is_space && !ABBREVIATIONS.contains(&word.as_str()) && !word.is_empty() {
+                    splitter.end(Boundary::Sentence, Mood::Statement);
+                } else {
+                    splitter.text.push(c);
+                }
+            }
+            // 破折号
+            '—' | '―' => splitter.end(Boundary::Comma, Mood::Statement),
+            '-' if run >= 2 => {
+                splitter.end(Boundary::Comma, Mood::Statement);
+                i += run;
+                continue;
+            }
+            // 换行：连续两个及以上为段落，否则为句子
+            '\n' | '\r' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_whitespace())
+                    .map_or(chars.len(), |offset| i + offset);
+                let newlines = chars[i..end].iter().filter(|&&c| c == '\n').count();
+                let boundary = if newlines >= 2 { Boundary::Paragraph } else { Boundary::Sentence };
+                splitter.end(boundary, Mood::Statement);
+                i = end;
+                continue;
+            }
+            _ => match punctuation(c) {
+                Some((boundary, mood)) => splitter.end(boundary, mood),
+                None => splitter.text.push(c),
+            },
+        }
+        i += 1;
+    }
+    splitter.end(Boundary::Sentence, Mood::Statement);
+    splitter.pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(text: &str, boundary: Boundary, mood: Mood) -> Piece {
+        Piece {
+            text: text.to_string(),
+            boundary,
+            mood,
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        use Boundary::*;
+        use Mood::*;
+        assert_eq!(
+            split("今天天气不错，有50%的概率会下雨！你带伞了吗？"),
+            vec![
+                piece("今天天气不错", Comma, Statement),
+                piece("有50%的概率会下雨", Sentence, Exclamation),
+                piece("你带伞了吗", Sentence, Question),
+            ]
+        );
+        // 连续标点合并，换行与空行分别为句子与段落边界
+        assert_eq!(
+            split("真的吗？！\n第二行\n\n  第二段；最后……"),
+            vec![
+                piece("真的吗", Sentence, Question),
+                piece("第二行", Paragraph, Statement),
+                piece("第二段", Comma, Statement),
+                piece("最后", Sentence, Statement),
+            ]
+        );
+        // 引号、书名号去掉，括号内容单独成句
+        assert_eq!(
+            split("他说：“我读过《三体》（刘慈欣著）。”"),
+            vec![
+                piece("他说", Comma, Statement),
+                piece("我读过三体", Comma, Statement),
+                piece("刘慈欣著", Sentence, Statement),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_english() {
+        use Boundary::*;
+        use Mood::*;
+        assert_eq!(
+            split("The price is $1,234.5 at 10:30. Mr. Smith didn’t agree... Really?"),
+            vec![
+                piece("The price is $1,234.5 at 10:30", Sentence, Statement),
+                piece("Mr. Smith didn't agree", Sentence, Statement),
+                piece("Really", Sentence, Question),
+            ]
+        );
+        assert_eq!(
+            split("Wait -- what, now"),
+            vec![
+                piece("Wait", Comma, Statement),
+                piece("what", Comma, Statement),
+                piece("now", Sentence, Statement),
+            ]
+        );
+        assert_eq!(split("  \n\n "), vec![]);
+    }
+
+    #[test]
+    fn test_pauses() {
+        let pauses = Pauses::default();
+        assert!(pauses.after(Boundary::Comma) < pauses.after(Boundary::Sentence));
+        assert!(pauses.after(Boundary::Sentence) < pauses.after(Boundary::Paragraph));
+        assert!(pauses.is_valid(5.0));
+        assert!(!Pauses { comma: -0.1, ..pauses }.is_valid(5.0));
+    }
+}
@@ -0,0 +1,508 @@
+// SSML 子集解析：把 <speak> 文档展开为带停顿、语言和韵律设置的分句，
+// 支持 break、say-as、sub、phoneme、lang、prosody，以及作为分句边界的 p、s
+use super::cn_tn::{Date, Digit, MobilePhone, TelePhone, DIGITS};
+use super::splitter::{self, Boundary, Mood};
+use super::tts_engine::TextType;
+use lazy_static::lazy_static;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+use std::collections::HashMap;
+
+// 单个停顿的上限（秒）
+pub const MAX_BREAK_TIME: f32 = 10.0;
+// 语速调整范围
+pub const MIN_RATE: f32 = 0.5;
+pub const MAX_RATE: f32 = 2.0;
+
+lazy_static! {
+    static ref ISO_DATE: Regex = Regex::new(r"^(\d{4})[-/.](\d{1,2})(?:[-/.](\d{1,2}))?$").unwrap();
+    static ref MOBILE: Regex = Regex::new(r"^(\+?86)?1([38]\d|5[0-35-9]|7[678]|9[89])\d{8}$").unwrap();
+    static ref LANDLINE: Regex = Regex::new(r"^(0(10|2[1-3]|[3-9]\d{2})-?)?[1-9]\d{6,7}$").unwrap();
+}
+
+// SSML 展开后的一个分句
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// 送入前端的文本，<phoneme> 展开为 {汉字|拼音} 或 {ARPAbet} 标记
+    pub text: String,
+    /// 用于语音标记与字幕的文本，<phoneme> 保留原文
+    pub display: String,
+    /// <lang> 指定的语言，None 时按文本自动判断
+    pub lang: Option<TextType>,
+    /// 分句前的停顿（秒），None 时按前一分句的边界类型确定
+    pub pause: Option<f32>,
+    /// 分句之后的边界，<p> 为段落，<s> 为句子，其余由标点决定
+    pub boundary: Boundary,
+    pub mood: Mood,
+    /// 语速倍率，1.0 为原速
+    pub rate: f32,
+    /// 音量增益（线性倍率）
+    pub volume: f32,
+}
+
+pub fn is_ssml(text: &str) -> bool {
+    text.trim_start().starts_with("<speak")
+}
+
+enum Node {
+    Text(String),
+    Element {
+        name: String,
+        attrs: HashMap<String, String>,
+        children: Vec<Node>,
+    },
+}
+
+impl Node {
+    // 元素内的纯文本
+    fn text(&self) -> String {
+        match self {
+            Node::Text(text) => text.clone(),
+            Node::Element { children, .. } => children.iter().map(Node::text).collect(),
+        }
+    }
+}
+
+fn element(start: &BytesStart) -> Result<(String, HashMap<String, String>), String> {
+    let name = String::from_utf8_lossy(start.local_name().as_ref()).to_string();
+    let mut attrs = HashMap::new();
+    for attr in start.attributes() {
+        let attr = attr.map_err(|e| e.to_string())?;
+        let value = attr.unescape_value().map_err(|e| e.to_string())?;
+        attrs.insert(String::from_utf8_lossy(attr.key.as_ref()).to_string(), value.to_string());
+    }
+    Ok((name, attrs))
+}
+
+fn parse_tree(ssml: &str) -> Result<Node, String> {
+    let mut reader = Reader::from_str(ssml);
+    // 栈底为虚拟根节点
+    let mut stack: Vec<(String, HashMap<String, String>, Vec<Node>)> = vec![(String::new(), HashMap::new(), Vec::new())];
+    loop {
+        match reader.read_event().map_err(|e| format!("invalid SSML at {}: {}", reader.buffer_position(), e))? {
+            Event::Start(start) => {
+                let (name, attrs) = element(&start)?;
+                stack.push((name, attrs, Vec::new()));
+            }
+            Event::Empty(start) => {
+                let (name, attrs) = element(&start)?;
+                stack.last_mut().unwrap().2.push(Node::Element { name, attrs, children: Vec::new() });
+            }
+            Event::End(_) => {
+                let (name, attrs, children) = stack.pop().unwrap();
+                stack.last_mut().ok_or("unbalanced SSML tags")?.2.push(Node::Element { name, attrs, children });
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| e.to_string())?;
+                stack.last_mut().unwrap().2.push(Node::Text(text.to_string()));
+            }
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data).to_string();
+                stack.last_mut().unwrap().2.push(Node::Text(text));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if stack.len() != 1 {
+        return Err("unclosed SSML tags".to_string());
+    }
+
+    let (_, _, children) = stack.pop().unwrap();
+    children
+        .into_iter()
+        .find(|node| matches!(node, Node::Element { name, .. } if name == "speak"))
+        .ok_or_else(|| "SSML must have a <speak> root element".to_string())
+}
+
+// 时长，如 500ms、1.5s
+fn parse_time(time: &str) -> Option<f32> {
+    let time = time.trim();
+    let seconds = if let Some(ms) = time.strip_suffix("ms") {
+        ms.trim().parse::<f32>().ok()? / 1000.0
+    } else {
+        time.strip_suffix('s').unwrap_or(time).trim().parse::<f32>().ok()?
+    };
+    (seconds >= 0.0).then_some(seconds.min(MAX_BREAK_TIME))
+}
+
+fn break_time(attrs: &HashMap<String, String>) -> Result<f32, String> {
+    if let Some(time) = attrs.get("time") {
+        return parse_time(time).ok_or_else(|| format!("invalid break time: {}", time));
+    }
+    match attrs.get("strength").map(String::as_str).unwrap_or("medium") {
+        "none" => Ok(0.0),
+        "x-weak" => Ok(0.1),
+        "weak" => Ok(0.2),
+        "medium" => Ok(0.4),
+        "strong" => Ok(0.7),
+        "x-strong" => Ok(1.0),
+        strength => Err(format!("invalid break strength: {}", strength)),
+    }
+}
+
+// 语速：x-slow..x-fast、百分比或倍率
+fn parse_rate(rate: &str) -> Result<f32, String> {
+    let value = match rate.trim() {
+        "x-slow" => 0.5,
+        "slow" => 0.75,
+        "medium" | "default" => 1.0,
+        "fast" => 1.25,
+        "x-fast" => 1.75,
+        rate => {
+            let parsed = match rate.strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f32>().map(|p| p / 100.0),
+                None => rate.parse::<f32>(),
+            };
+            parsed.map_err(|_| format!("invalid prosody rate: {}", rate))?
+        }
+    };
+    Ok(value.clamp(MIN_RATE, MAX_RATE))
+}
+
+// 音量：silent..x-loud 或 +6dB 形式的增益
+fn parse_volume(volume: &str) -> Result<f32, String> {
+    let db = match volume.trim() {
+        "silent" => return Ok(0.0),
+        "x-soft" => -12.0,
+        "soft" => -6.0,
+        "medium" | "default" => 0.0,
+        "loud" => 6.0,
+        "x-loud" => 12.0,
+        volume => volume
+            .strip_suffix("dB")
+            .and_then(|db| db.trim().parse::<f32>().ok())
+            .ok_or_else(|| format!("invalid prosody volume: {}", volume))?,
+    };
+    Ok(10f32.powf(db.clamp(-40.0, 20.0) / 20.0))
+}
+
+fn parse_lang(lang: &str) -> Result<TextType, String> {
+    let lower = lang.to_ascii_lowercase();
+    if lower.starts_with("zh") || lower.starts_with("cmn") {
+        Ok(TextType::Chinese)
+    } else if lower.starts_with("en") {
+        Ok(TextType::English)
+    } else {
+        Err(format!("unsupported language: {}", lang))
+    }
+}
+
+fn chinese_digit(c: char) -> Option<&'static str> {
+    c.to_digit(10).map(|d| DIGITS[&(d as u8)][0])
+}
+
+// 逐个数字朗读，与 cn_tn 中电话号码的读法一致
+fn read_digits(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match chinese_digit(c) {
+            Some(digit) => Some(digit.to_string()),
+            None if c.is_whitespace() || c == '-' || c == '+' => None,
+            None => Some(c.to_string()),
+        })
+        .collect()
+}
+
+// say-as：中文调用 cn_tn 中对应的转换器，英文交给 LJSpeech 的文本清洗
+fn say_as(text: &str, interpret_as: &str, lang: Option<TextType>) -> String {
+    let text = text.trim();
+    if lang == Some(TextType::English) {
+        return match interpret_as {
+            "characters" | "digits" | "telephone" => text
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => text.to_string(),
+        };
+    }
+
+    match interpret_as {
+        "cardinal" | "number" => Digit::new(text.replace(',', "")).to_chntext().unwrap_or(text).to_string(),
+        "digits" => read_digits(text),
+        "characters" => text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| chinese_digit(c).map(String::from).unwrap_or_else(|| c.to_string()))
+            .collect(),
+        "date" => {
+            let date = match ISO_DATE.captures(text) {
+                Some(caps) => match caps.get(3) {
+                    Some(day) => format!("{}年{}月{}日", &caps[1], &caps[2], day.as_str()),
+                    None => format!("{}年{}月", &caps[1], &caps[2]),
+                },
+                None => text.to_string(),
+            };
+            Date::new(date.clone()).to_chntext().unwrap_or(&date).to_string()
+        }
+        "telephone" => {
+            let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+            let converted = if MOBILE.is_match(&compact) {
+                MobilePhone::new(compact.clone()).to_chntext().map(String::from)
+            } else if LANDLINE.is_match(&compact) {
+                TelePhone::new(compact.clone()).to_chntext().map(String::from)
+            } else {
+                None
+            };
+            // 转换器未覆盖的部分逐位朗读
+            read_digits(&converted.unwrap_or(compact))
+        }
+        _ => text.to_string(),
+    }
+}
+
+// 指定读音：拼音写成 {汉字|pin1 yin1}，ARPAbet 写成 LJSpeech 支持的 {HH AH0}
+fn phoneme(text: &str, attrs: &HashMap<String, String>) -> Result<String, String> {
+    let ph = attrs.get("ph").ok_or("phoneme requires a ph attribute")?.trim();
+    match attrs.get("alphabet").map(String::as_str) {
+        Some("pinyin") => {
+            let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+            let syllables = ph.split_whitespace().count();
+            if chars != syllables {
+                return Err(format!("phoneme {:?} has {} syllables for {} characters", ph, syllables, chars));
+            }
+            Ok(format!("{{{}|{}}}", text.trim(), ph))
+        }
+        Some("arpabet") => Ok(format!("{{{}}}", ph)),
+        alphabet => Err(format!("unsupported phoneme alphabet: {:?}", alphabet)),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Context {
+    lang: Option<TextType>,
+    rate: f32,
+    volume: f32,
+}
+
+struct Builder {
+    segments: Vec<Segment>,
+    text: String,
+    display: String,
+    context: Context,
+    pause: Option<f32>,
+}
+
+impl Builder {
+    fn flush(&mut self) {
+        self.end(Boundary::Comma, Mood::Statement);
+    }
+
+    // 结束当前分句；没有文本时把边界与语气合并到上一个分句
+    fn end(&mut self, boundary: Boundary, mood: Mood) {
+        let text = self.text.trim().to_string();
+        let display = self.display.trim().to_string();
+        self.text.clear();
+        self.display.clear();
+        if text.is_empty() {
+            if let Some(last) = self.segments.last_mut() {
+                last.boundary = last.boundary.max(boundary);
+                if last.mood == Mood::Statement {
+                    last.mood = mood;
+                }
+            }
+            return;
+        }
+        self.segments.push(Segment {
+            text,
+            display,
+            lang: self.context.lang,
+            pause: self.pause.take(),
+            rate: self.context.rate,
+            volume: self.context.volume,
+            boundary,
+            mood,
+        });
+    }
+
+    // 语言或韵律变化时另起一个分句，中间不插入停顿
+    fn set_context(&mut self, context: Context) {
+        if context != self.context && !self.text.trim().is_empty() {
+            self.flush();
+            self.pause.get_or_insert(0.0);
+        }
+        self.context = context;
+    }
+
+    fn push_text(&mut self, text: &str, context: Context) {
+        self.set_context(context);
+        for c in text.chars() {
+            match splitter::punctuation(c) {
+                Some((boundary, mood)) => self.end(boundary, mood),
+                None => {
+                    self.text.push(c);
+                    self.display.push(c);
+                }
+            }
+        }
+    }
+
+    // 整体作为一个不可拆分的片段加入当前分句，display 为标记与字幕中显示的文本
+    fn push_atom(&mut self, text: &str, display: &str, context: Context) {
+        self.set_context(context);
+        self.text.push_str(text);
+        self.display.push_str(display);
+    }
+
+    fn push_break(&mut self, seconds: f32) {
+        self.flush();
+        *self.pause.get_or_insert(0.0) += seconds;
+    }
+
+    fn walk(&mut self, node: &Node, context: Context) -> Result<(), String> {
+        let (name, attrs, children) = match node {
+            Node::Text(text) => {
+                self.push_text(text, context);
+                return Ok(());
+            }
+            Node::Element { name, attrs, children } => (name.as_str(), attrs, children),
+        };
+
+        let mut inner = context;
+        match name {
+            "break" => {
+                self.push_break(break_time(attrs)?);
+                return Ok(());
+            }
+            "say-as" => {
+                let interpret_as = attrs.get("interpret-as").ok_or("say-as requires interpret-as")?;
+                let text = say_as(&node.text(), interpret_as, context.lang);
+                self.push_atom(&text, &text, context);
+                return Ok(());
+            }
+            "sub" => {
+                let alias = attrs.get("alias").ok_or("sub requires an alias attribute")?;
+                self.push_text(alias, context);
+                return Ok(());
+            }
+            "phoneme" => {
+                let display = node.text();
+                let text = phoneme(&display, attrs)?;
+                self.push_atom(&text, display.trim(), context);
+                return Ok(());
+            }
+            "lang" => {
+                let lang = attrs.get("xml:lang").or_else(|| attrs.get("lang")).ok_or("lang requires xml:lang")?;
+                inner.lang = Some(parse_lang(lang)?);
+            }
+            "prosody" => {
+                if let Some(rate) = attrs.get("rate") {
+                    inner.rate = parse_rate(rate)?;
+                }
+                if let Some(volume) = attrs.get("volume") {
+                    inner.volume = parse_volume(volume)?;
+                }
+            }
+            "speak" => {
+                if let Some(lang) = attrs.get("xml:lang") {
+                    inner.lang = parse_lang(lang).ok();
+                }
+            }
+            "p" | "s" => self.flush(),
+            _ => {}
+        }
+
+        for child in children {
+            self.walk(child, inner)?;
+        }
+        match name {
+            "p" => self.end(Boundary::Paragraph, Mood::Statement),
+            "s" => self.end(Boundary::Sentence, Mood::Statement),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// 解析 SSML 文档，返回按顺序排列的分句；文档末尾的停顿以空文本分句表示
+pub fn parse(ssml: &str) -> Result<Vec<Segment>, String> {
+    let root = parse_tree(ssml)?;
+    let context = Context { lang: None, rate: 1.0, volume: 1.0 };
+    let mut builder = Builder {
+        segments: Vec::new(),
+        text: String::new(),
+        display: String::new(),
+        context,
+        pause: None,
+    };
+    builder.walk(&root, context)?;
+    builder.flush();
+    if let Some(pause) = builder.pause.filter(|pause| *pause > 0.0) {
+        builder.segments.push(Segment {
+            text: String::new(),
+            display: String::new(),
+            lang: None,
+            pause: Some(pause),
+            rate: 1.0,
+            volume: 1.0,
+            boundary: Boundary::Paragraph,
+            mood: Mood::Statement,
+        });
+    }
+    Ok(builder.segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_breaks() {
+        let segments = parse(r#"<speak>你好，欢迎<break time="500ms"/>光临<break strength="strong"/><break time="1s"/></speak>"#).unwrap();
+        assert_eq!(texts(&segments), vec!["你好", "欢迎", "光临", ""]);
+        assert_eq!(segments[0].pause, None);
+        assert_eq!(segments[1].pause, None);
+        assert_eq!(segments[2].pause, Some(0.5));
+        assert_eq!(segments[3].pause, Some(1.7));
+    }
+
+    #[test]
+    fn test_parse_say_as() {
+        let segments = parse(
+            r#"<speak>编号<say-as interpret-as="digits">2024</say-as>，共<say-as interpret-as="cardinal">2024</say-as>个，日期<say-as interpret-as="date">2024-05-01</say-as>，电话<say-as interpret-as="telephone">13812345678</say-as></speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            texts(&segments),
+            vec!["编号二零二四", "共二千零二十四个", "日期二零二四年五月一日", "电话一三八一二三四五六七八"]
+        );
+    }
+
+    #[test]
+    fn test_parse_sub_phoneme_lang() {
+        let segments = parse(
+            r#"<speak><sub alias="世界卫生组织">WHO</sub>的<phoneme alphabet="pinyin" ph="zhong4 yao4">重要</phoneme>通知。<lang xml:lang="en-US">read <phoneme alphabet="arpabet" ph="R EH1 D">read</phoneme></lang></speak>"#,
+        )
+        .unwrap();
+        assert_eq!(texts(&segments), vec!["世界卫生组织的{重要|zhong4 yao4}通知", "read {R EH1 D}"]);
+        let displays: Vec<&str> = segments.iter().map(|segment| segment.display.as_str()).collect();
+        assert_eq!(displays, vec!["世界卫生组织的重要通知", "read read"]);
+        assert_eq!(segments[0].lang, None);
+        assert_eq!(segments[1].lang, Some(TextType::English));
+        assert!(parse(r#"<speak><phoneme alphabet="pinyin" ph="zhong4">重要</phoneme></speak>"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_prosody() {
+        let segments = parse(r#"<speak>正常<prosody rate="slow" volume="+6dB">慢一点</prosody>正常</speak>"#).unwrap();
+        assert_eq!(texts(&segments), vec!["正常", "慢一点", "正常"]);
+        assert_eq!(segments[1].pause, Some(0.0));
+        assert_eq!(segments[1].rate, 0.75);
+        assert!((segments[1].volume - 1.995).abs() < 0.01);
+        assert_eq!(segments[2].rate, 1.0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("<speak>未闭合").is_err());
+        assert!(parse("<foo>不是 speak</foo>").is_err());
+        assert!(parse(r#"<speak><break time="abc"/></speak>"#).is_err());
+        assert!(parse(r#"<speak><prosody rate="fastest">快</prosody></speak>"#).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use super::error::{Result, TTSError};
+use super::interpreter::{InterpreterPool, PooledInterpreter};
+use super::model::{AcousticModel, AcousticOutput, Prosody};
+use super::timing::Alignment;
+use tflite::TensorIndex;
+
+// Tacotron2 输出末尾含有噪声，需要截掉的采样数
+pub const TACOTRON_TAIL_SAMPLES: usize = 2048;
+// 梅尔谱（postnet 之后）与 attention 对齐在输出中的位置
+const MEL_OUTPUT: usize = 1;
+const ALIGNMENT_OUTPUT: usize = 3;
+
+// 模型输入的含义
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Input {
+    Ids,
+    Lengths,
+    Speaker,
+}
+
+impl Input {
+    // 按张量名识别输入，名称无法识别时按输入顺序：input_ids、input_lengths、speaker_ids
+    fn resolve(name: Option<&str>, position: usize) -> Option<Input> {
+        match name {
+            Some(name) if name.contains("input_ids") => Some(Input::Ids),
+            Some(name) if name.contains("input_lengths") => Some(Input::Lengths),
+            Some(name) if name.contains("speaker_ids") => Some(Input::Speaker),
+            _ => [Input::Ids, Input::Lengths, Input::Speaker].get(position).copied(),
+        }
+    }
+}
+
+// TensorFlowTTS 导出的 Tacotron2：自回归解码，输出梅尔谱与 attention 对齐
+pub struct Tacotron2 {
+    pool: InterpreterPool,
+    num_speakers: Option<usize>,
+}
+
+impl Tacotron2 {
+    pub fn new(pool: InterpreterPool) -> Result<Self> {
+        let num_speakers = pool.speaker_count()?;
+        Ok(Self { pool, num_speakers })
+    }
+
+    fn inputs(interpreter: &PooledInterpreter) -> Vec<(TensorIndex, Option<Input>)> {
+        interpreter
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| {
+                let name = interpreter.tensor_info(index).map(|info| info.name);
+                (index, Input::resolve(name.as_deref(), position))
+            })
+            .collect()
+    }
+}
+
+impl AcousticModel for Tacotron2 {
+    fn infer(&self, input_ids: &[i32], speaker: i32, _prosody: &Prosody) -> Result<AcousticOutput> {
+        self.pool.run(|interpreter| {
+            let inputs = Self::inputs(interpreter);
+            let ids_index = inputs
+                .iter()
+                .find(|(_, input)| *input == Some(Input::Ids))
+                .map(|(index, _)| *index)
+                .ok_or_else(|| TTSError::Inference("acoustic model has no input_ids input".to_string()))?;
+            interpreter.resize_input(ids_index, &[1, input_ids.len() as i32])?;
+
+            for (index, input) in &inputs {
+                let input: &[i32] = match input {
+                    Some(Input::Ids) => input_ids,
+                    Some(Input::Lengths) => &[input_ids.len() as i32],
+                    Some(Input::Speaker) => &[speaker],
+                    None => continue,
+                };
+                let tensor: &mut [i32] = interpreter.tensor_data_mut(*index)?;
+                if tensor.len() != input.len() {
+                    return Err(TTSError::Inference(format!(
+                        "acoustic model input {} expects {} values, got {}",
+                        index,
+                        tensor.len(),
+                        input.len()
+                    )));
+                }
+                tensor.copy_from_slice(input);
+            }
+
+            interpreter.invoke()?;
+
+            let outputs = interpreter.outputs().to_vec();
+            let output_index = *outputs
+                .get(MEL_OUTPUT)
+                .ok_or_else(|| TTSError::Inference("acoustic model has no mel output".to_string()))?;
+            let mel: &[f32] = interpreter.tensor_data(output_index)?;
+
+            // attention 对齐的形状为 [1, input_len, steps]
+            let alignment = outputs.get(ALIGNMENT_OUTPUT).and_then(|&index| {
+                let info = interpreter.tensor_info(index)?;
+                let weights: &[f32] = interpreter.tensor_data(index).ok()?;
+                Alignment::from_tensor(weights, &info.dims)
+            });
+
+            Ok(AcousticOutput {
+                mel: mel.to_owned(),
+                alignment,
+            })
+        })
+    }
+
+    fn num_speakers(&self) -> Option<usize> {
+        self.num_speakers
+    }
+
+    fn autoregressive(&self) -> bool {
+        true
+    }
+
+    fn tail_samples(&self) -> usize {
+        TACOTRON_TAIL_SAMPLES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_input() {
+        assert_eq!(Input::resolve(Some("serving_default_speaker_ids:0"), 0), Some(Input::Speaker));
+        assert_eq!(Input::resolve(Some("input_lengths"), 2), Some(Input::Lengths));
+        assert_eq!(Input::resolve(None, 0), Some(Input::Ids));
+        assert_eq!(Input::resolve(Some("unknown"), 2), Some(Input::Speaker));
+        assert_eq!(Input::resolve(None, 3), None);
+    }
+}
@@ -0,0 +1,312 @@
+// 合成音频的时间标注：由 Tacotron2 的 attention 对齐计算每个字/词的起止时间，
+// 并输出为 JSON 语音标记、SRT 或 WebVTT 字幕
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+// 文本单元（汉字或英文单词）及其在模型输入序列中的下标区间
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextUnit {
+    pub text: String,
+    pub tokens: Range<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkType {
+    /// 分句
+    Sentence,
+    /// 汉字或英文单词
+    Word,
+}
+
+// 语音标记，时间相对于整段输出音频的开头
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct SpeechMark {
+    #[serde(rename = "type")]
+    pub mark_type: MarkType,
+    /// 标记对应的文本
+    pub value: String,
+    /// 开始时间（毫秒）
+    pub start_ms: u64,
+    /// 结束时间（毫秒）
+    pub end_ms: u64,
+}
+
+// 字幕格式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MarksFormat {
+    /// JSON 语音标记
+    #[default]
+    Json,
+    /// SubRip 字幕，每个分句一条
+    Srt,
+    /// WebVTT 字幕，每个分句一条，字/词的时间以内嵌时间戳给出
+    #[serde(alias = "webvtt")]
+    Vtt,
+}
+
+// attention 对齐矩阵，布局为 [input_len, steps]
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    weights: Vec<f32>,
+    input_len: usize,
+    steps: usize,
+}
+
+impl Alignment {
+    // dims 为模型输出的形状 [1, input_len, steps]
+    pub fn from_tensor(weights: &[f32], dims: &[usize]) -> Option<Self> {
+        let (input_len, steps) = match dims {
+            [1, input_len, steps] => (*input_len, *steps),
+            _ => return None,
+        };
+        if input_len == 0 || steps == 0 || weights.len() != input_len * steps {
+            return None;
+        }
+        Some(Self {
+            weights: weights.to_vec(),
+            input_len,
+            steps,
+        })
+    }
+
+    // 由每个 token 持续的帧数构造硬对齐，用于 FastSpeech2 等非自回归模型
+    pub fn from_durations(durations: &[i32]) -> Option<Self> {
+        let input_len = durations.len();
+        let steps = durations.iter().map(|&d| d.max(0) as usize).sum::<usize>();
+        if input_len == 0 || steps == 0 {
+            return None;
+        }
+        let mut weights = vec![0.0; input_len * steps];
+        let mut step = 0;
+        for (token, &duration) in durations.iter().enumerate() {
+            for _ in 0..duration.max(0) {
+                weights[token * steps + step] = 1.0;
+                step += 1;
+            }
+        }
+        Some(Self {
+            weights,
+            input_len,
+            steps,
+        })
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // 每个输入 token 开始发音的解码步，末尾追加总步数，共 input_len + 1 项。
+    // 每一步取权重最大的 token，并强制单调递增以消除 attention 的回跳
+    pub fn token_boundaries(&self) -> Vec<usize> {
+        let mut boundaries = vec![self.steps; self.input_len + 1];
+        boundaries[0] = 0;
+        let mut current = 0;
+        for step in 0..self.steps {
+            let best = (0..self.input_len)
+                .max_by(|&a, &b| {
+                    self.weights[a * self.steps + step].total_cmp(&self.weights[b * self.steps + step])
+                })
+                .unwrap_or_default();
+            while current < best {
+                current += 1;
+                boundaries[current] = step;
+            }
+        }
+        boundaries
+    }
+}
+
+// 计算一个分句内各文本单元的时间标记。
+// step_sec 为每个解码步对应的秒数，duration 为裁剪后分句的实际时长，时间相对于分句开头
+pub fn unit_marks(units: &[TextUnit], boundaries: &[usize], step_sec: f32, duration: f32) -> Vec<SpeechMark> {
+    units
+        .iter()
+        .filter(|unit| unit.tokens.start < unit.tokens.end && unit.tokens.end < boundaries.len())
+        .filter_map(|unit| {
+            let start = (boundaries[unit.tokens.start] as f32 * step_sec).min(duration);
+            let end = (boundaries[unit.tokens.end] as f32 * step_sec).min(duration);
+            // 被尾部裁剪掉的单元不再输出
+            if start >= duration {
+                return None;
+            }
+            Some(SpeechMark {
+                mark_type: MarkType::Word,
+                value: unit.text.clone(),
+                start_ms: to_ms(start),
+                end_ms: to_ms(end),
+            })
+        })
+        .collect()
+}
+
+pub fn to_ms(seconds: f32) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+impl SpeechMark {
+    // 平移到整段音频中的时间
+    pub fn shifted(mut self, offset_ms: u64) -> Self {
+        self.start_ms += offset_ms;
+        self.end_ms += offset_ms;
+        self
+    }
+
+    // 按比例缩放时间，用于变速后的音频
+    pub fn scaled(mut self, factor: f32) -> Self {
+        self.start_ms = (self.start_ms as f32 * factor).round() as u64;
+        self.end_ms = (self.end_ms as f32 * factor).round() as u64;
+        self
+    }
+}
+
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+pub fn to_srt(marks: &[SpeechMark]) -> String {
+    marks
+        .iter()
+        .filter(|mark| mark.mark_type == MarkType::Sentence)
+        .enumerate()
+        .map(|(i, mark)| {
+            format!(
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                timestamp(mark.start_ms, ','),
+                timestamp(mark.end_ms, ','),
+                mark.value
+            )
+        })
+        .collect()
+}
+
+pub fn to_webvtt(marks: &[SpeechMark]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for sentence in marks.iter().filter(|mark| mark.mark_type == MarkType::Sentence) {
+        let words: Vec<&SpeechMark> = marks
+            .iter()
+            .filter(|mark| {
+                mark.mark_type == MarkType::Word
+                    && mark.start_ms >= sentence.start_ms
+                    && mark.start_ms < sentence.end_ms
+            })
+            .collect();
+        // 有字/词时间时使用内嵌时间戳，便于卡拉OK式逐字高亮
+        let text = if words.is_empty() {
+            sentence.value.clone()
+        } else {
+            let separator = if words.iter().any(|word| word.value.is_ascii()) { " " } else { "" };
+            words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.value.clone()
+                    } else {
+                        format!("<{}>{}", timestamp(word.start_ms, '.'), word.value)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        vtt.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(sentence.start_ms, '.'),
+            timestamp(sentence.end_ms, '.'),
+            text
+        ));
+    }
+    vtt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(mark_type: MarkType, value: &str, start_ms: u64, end_ms: u64) -> SpeechMark {
+        SpeechMark {
+            mark_type,
+            value: value.to_string(),
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn test_token_boundaries() {
+        // 3 个 token，6 个解码步：0,0,1,1,1,2
+        #[rustfmt::skip]
+        let weights = [
+            0.9, 0.8, 0.1, 0.0, 0.0, 0.0,
+            0.1, 0.2, 0.7, 0.9, 0.6, 0.1,
+            0.0, 0.0, 0.2, 0.1, 0.4, 0.9,
+        ];
+        let alignment = Alignment::from_tensor(&weights, &[1, 3, 6]).unwrap();
+        assert_eq!(alignment.token_boundaries(), vec![0, 2, 5, 6]);
+        assert!(Alignment::from_tensor(&weights, &[1, 2, 6]).is_none());
+    }
+
+    #[test]
+    fn test_token_boundaries_skip() {
+        // token 1 没有获得任何解码步，起止时间相同
+        #[rustfmt::skip]
+        let weights = [
+            0.9, 0.1, 0.0,
+            0.1, 0.2, 0.1,
+            0.0, 0.7, 0.9,
+        ];
+        let alignment = Alignment::from_tensor(&weights, &[1, 3, 3]).unwrap();
+        assert_eq!(alignment.token_boundaries(), vec![0, 1, 1, 3]);
+    }
+
+    #[test]
+    fn test_duration_alignment() {
+        let alignment = Alignment::from_durations(&[2, 0, 3, 1]).unwrap();
+        assert_eq!(alignment.steps(), 6);
+        assert_eq!(alignment.token_boundaries(), vec![0, 2, 2, 5, 6]);
+        assert!(Alignment::from_durations(&[0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_unit_marks() {
+        let units = vec![
+            TextUnit { text: "你".to_string(), tokens: 1..3 },
+            TextUnit { text: "好".to_string(), tokens: 4..6 },
+        ];
+        let boundaries = vec![0, 2, 4, 6, 8, 10, 20];
+        let marks: Vec<SpeechMark> = unit_marks(&units, &boundaries, 0.01, 0.09)
+            .into_iter()
+            .map(|mark| mark.shifted(1000))
+            .collect();
+        assert_eq!(marks, vec![mark(MarkType::Word, "你", 1020, 1060), mark(MarkType::Word, "好", 1080, 1090)]);
+        // 尾部裁剪后不再发音的单元被丢弃
+        assert_eq!(unit_marks(&units, &boundaries, 0.01, 0.05).len(), 1);
+    }
+
+    #[test]
+    fn test_subtitles() {
+        let marks = vec![
+            mark(MarkType::Sentence, "hello world", 0, 1500),
+            mark(MarkType::Word, "hello", 0, 600),
+            mark(MarkType::Word, "world", 700, 1500),
+            mark(MarkType::Sentence, "你好", 3_601_700, 3_602_500),
+        ];
+        assert_eq!(
+            to_srt(&marks),
+            "1\n00:00:00,000 --> 00:00:01,500\nhello world\n\n2\n01:00:01,700 --> 01:00:02,500\n你好\n\n"
+        );
+        assert_eq!(
+            to_webvtt(&marks),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nhello <00:00:00.700>world\n\n01:00:01.700 --> 01:00:02.500\n你好\n\n"
+        );
+    }
+}
@@ -1,277 +1,643 @@
-use super::baker::BakerProcessor;
-use super::ljspeech::LJSpeechProcessor;
-use regex::Regex;
-use rubato::{
-    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
-};
-use serde::Deserialize;
-use tflite::ops::builtin::BuiltinOpResolver;
-use tflite::{FlatBufferModel, InterpreterBuilder};
+use super::audio::{self, f32_to_i16};
+use super::chunk::{self, DEFAULT_MAX_CLAUSE_LEN};
+use super::error::{Result, TTSError};
+use super::mb_melgan::NUM_MELS;
+use super::mel::ClauseMel;
+use super::model::Prosody;
+use super::quality::{check_decode, DecodeIssue, DecodeWarning};
+use super::splitter::{self, Boundary, Mood, Pauses};
+use super::ssml;
+use super::timing::{to_ms, unit_marks, Alignment, MarkType, SpeechMark};
+use super::voice::{Voice, VoiceConfig};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
 pub enum TextType {
+    #[serde(alias = "zh", alias = "chinese")]
     Chinese,
+    #[serde(alias = "en", alias = "english")]
     English,
 }
 
-pub struct TTSEngine {
-    sample_rate: usize,
-    processor_cn: BakerProcessor,
-    processor_en: LJSpeechProcessor,
-    text2mel_name: &'static str,
-    acoustic_model: [FlatBufferModel; 2], // Replace with actual TensorFlow Lite Interpreter type
-    vocoder_model: [FlatBufferModel; 2],  // Replace with actual TensorFlow Lite Interpreter type
+// 配置的边界停顿上限（秒）
+const MAX_PAUSE: f32 = 10.0;
+// 过长分句拆开后片段之间的停顿（秒），不超过 sil_time
+const CHUNK_PAUSE: f32 = 0.05;
+// 解码失败后对半拆分重试的最大层数
+const MAX_RETRY_DEPTH: usize = 2;
+
+// 单次合成的参数
+#[derive(Debug, Clone)]
+pub struct SynthesisParams {
+    /// 分句之间统一的停顿时长（秒），None 时按前一分句的边界类型使用引擎配置的停顿
+    pub sil_time: Option<f32>,
+    /// 强制使用的语言，None 时按分句自动判断
+    pub text_type: Option<TextType>,
+    /// 输出采样率，None 时使用引擎采样率
+    pub sample_rate: Option<usize>,
+    /// 音色名称，None 或与分句语言不符时使用该语言的第一个音色
+    pub voice: Option<String>,
+    /// 说话人名称，None 或不属于所用音色时使用音色的默认说话人
+    pub speaker: Option<String>,
+    /// 语速倍率，与 SSML 的语速相乘
+    pub speed: f32,
+    /// 音高倍率，仅 FastSpeech2 等可控制韵律的模型生效
+    pub pitch: f32,
+    /// 能量倍率，仅 FastSpeech2 等可控制韵律的模型生效
+    pub energy: f32,
 }
 
-impl TTSEngine {
-    pub fn new() -> Self {
-        let melgen_tflite = "assets/mb_melgan.baker.tflite";
-        let melgen_model = FlatBufferModel::build_from_file(melgen_tflite).unwrap();
+impl Default for SynthesisParams {
+    fn default() -> Self {
+        Self {
+            sil_time: None,
+            text_type: None,
+            sample_rate: None,
+            voice: None,
+            speaker: None,
+            speed: 1.0,
+            pitch: 1.0,
+            energy: 1.0,
+        }
+    }
+}
 
-        let tacotron2_tflite = "assets/tacotron2.baker_quan.tflite";
-        let tacotron2_model = FlatBufferModel::build_from_file(tacotron2_tflite).unwrap();
+// 待合成的分句，纯文本按标点拆分得到，SSML 文档由解析结果得到
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    /// 送入前端的文本，可能含有 SSML <phoneme> 展开的读音标记
+    pub text: String,
+    /// 语音标记与字幕中显示的文本，不含读音标记
+    pub display: String,
+    pub text_type: TextType,
+    /// 分句前的停顿（秒），None 时首句不停顿、其余分句使用逗号级的停顿
+    pub pause: Option<f32>,
+    /// 语速倍率，1.0 为原速
+    pub rate: f32,
+    /// 音量增益（线性倍率）
+    pub volume: f32,
+    /// 分句之后的边界，决定下一分句前的停顿
+    pub boundary: Boundary,
+    /// 由句末标点决定的语气，问句与感叹句可用于调整韵律
+    pub mood: Mood,
+}
 
-        let melgen_ljspeech_tflite = "assets/mb_melgan.ljspeech.tflite";
-        let melgen_ljspeech_model =
-            FlatBufferModel::build_from_file(melgen_ljspeech_tflite).unwrap();
+impl Clause {
+    fn plain(text: String, text_type: TextType, boundary: Boundary, mood: Mood) -> Self {
+        Self {
+            display: text.clone(),
+            text,
+            text_type,
+            pause: None,
+            rate: 1.0,
+            volume: 1.0,
+            boundary,
+            mood,
+        }
+    }
+}
 
-        let tacotron2_ljspeech_tflite = "assets/tacotron2.ljspeech_quan.tflite";
-        let tacotron2_ljspeech_model =
-            FlatBufferModel::build_from_file(tacotron2_ljspeech_tflite).unwrap();
+// 只包含英文字母、标点和空白的分句按英文处理，其余按中文处理
+pub fn detect_text_type(text: &str) -> TextType {
+    if text
+        .chars()
+        .all(|c| c.is_ascii_alphabetic() || c.is_ascii_punctuation() || c.is_whitespace())
+    {
+        TextType::English
+    } else {
+        TextType::Chinese
+    }
+}
 
-        let acoustic_model = [tacotron2_model, tacotron2_ljspeech_model];
-        let vocoder_model = [melgen_model, melgen_ljspeech_model];
+// 引擎的运行参数
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    /// 每个模型预先构建的解释器数量，一般与合成线程数相同
+    pub interpreters: usize,
+    /// 每个解释器的推理线程数
+    pub threads: i32,
+    /// 单次请求中同时合成的分句数，1 表示逐句合成
+    pub parallelism: usize,
+    /// 要加载的音色，未指定音色时每种语言使用列表中的第一个音色
+    pub voices: Vec<VoiceConfig>,
+    /// 音色中相对路径的根目录，None 时按工作目录解析
+    pub model_dir: Option<PathBuf>,
+    /// 分句长度上限（汉字数 + 英文词数），超过时按词或韵律边界拆开，0 表示不拆
+    pub max_clause_len: usize,
+    /// 逗号、句子与段落边界之后的停顿
+    pub pauses: Pauses,
+}
 
-        // Implement TTS constructor
+impl Default for EngineOptions {
+    fn default() -> Self {
         Self {
-            sample_rate: 24000,
-            processor_cn: BakerProcessor::new().unwrap(),
-            processor_en: LJSpeechProcessor::new().unwrap(),
-            text2mel_name: "TACOTRON",
-            acoustic_model,
-            vocoder_model,
+            interpreters: 1,
+            threads: 1,
+            parallelism: 1,
+            voices: VoiceConfig::builtin(),
+            model_dir: None,
+            max_clause_len: DEFAULT_MAX_CLAUSE_LEN,
+            pauses: Pauses::default(),
         }
     }
+}
 
-    pub fn split_sens(&self, text: &str) -> Vec<(String, TextType)> {
-        // 创建正则表达式对象
-        let re_sep = Regex::new(r"([、，。！？：,!?])").unwrap();
+// 输出采样率下的分句音频、相对于分句开头的语音标记、分句时长（毫秒）以及解码检查的警告
+type RenderedClause = (Vec<i16>, Vec<SpeechMark>, u64, Vec<DecodeWarning>);
 
-        let re_ignore = ['"'];
-        // 过滤掉英文标点符号以及空白符
-        let processed_text: String = text
-            .trim()
-            .chars()
-            .filter(|&c| !re_ignore.contains(&c))
-            .collect();
+// 引擎采样率下的音频、语音标记与未通过检测的文本
+type CheckedAudio = (Vec<i16>, Vec<SpeechMark>, Vec<(String, DecodeIssue)>);
+
+// 整段合成的结果
+#[derive(Debug, Clone, Default)]
+pub struct Synthesis {
+    pub audio: Vec<i16>,
+    pub marks: Vec<SpeechMark>,
+    /// 重试后仍未通过解码检查的片段
+    pub warnings: Vec<DecodeWarning>,
+}
+
+pub struct TTSEngine {
+    sample_rate: usize,
+    voices: Vec<Voice>,
+    parallelism: usize,
+    max_clause_len: usize,
+    pauses: Pauses,
+    // 并行合成分句的线程池，parallelism 为 1 时不创建
+    clause_pool: Option<rayon::ThreadPool>,
+}
+
+impl TTSEngine {
+    pub fn new() -> Result<Self> {
+        Self::with_options(&EngineOptions::default())
+    }
+
+    pub fn with_options(options: &EngineOptions) -> Result<Self> {
+        if options.voices.is_empty() {
+            return Err(TTSError::ModelLoad("no voice configured".to_string()));
+        }
+        if !options.pauses.is_valid(MAX_PAUSE) {
+            return Err(TTSError::ModelLoad(format!("pauses must be within [0, {}] seconds", MAX_PAUSE)));
+        }
+        for (i, config) in options.voices.iter().enumerate() {
+            if options.voices[..i].iter().any(|other| other.name == config.name) {
+                return Err(TTSError::ModelLoad(format!("duplicate voice {}", config.name)));
+            }
+        }
+        let voices = options
+            .voices
+            .iter()
+            .map(|config| Voice::load(config, options))
+            .collect::<Result<Vec<_>>>()?;
+        let parallelism = options.parallelism.max(1);
+        let clause_pool = (parallelism > 1)
+            .then(|| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(parallelism)
+                    .thread_name(|i| format!("tts-clause-{}", i))
+                    .build()
+                    .map_err(|e| TTSError::ModelLoad(format!("failed to spawn clause threads: {}", e)))
+            })
+            .transpose()?;
+
+        // Implement TTS constructor
+        Ok(Self {
+            sample_rate: 24000,
+            voices,
+            parallelism,
+            max_clause_len: options.max_clause_len,
+            pauses: options.pauses,
+            clause_pool,
+        })
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
 
-        // 将匹配的分隔符替换为";"，并在每个分隔符后添加";"
-        let processed_text = re_sep.replace_all(processed_text.trim(), |_caps: &regex::Captures| {
-            // format!("{};", &caps[1])
-            String::from(";")
-        });
+    // 根据音色名查找音色
+    pub fn find_voice(&self, name: &str) -> Option<&Voice> {
+        self.voices.iter().find(|v| v.name == name)
+    }
+
+    // 查找包含该说话人的第一个音色
+    pub fn speaker_voice(&self, speaker: &str) -> Option<&Voice> {
+        self.voices.iter().find(|v| v.has_speaker(speaker))
+    }
+
+    pub fn pauses(&self) -> &Pauses {
+        &self.pauses
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
 
-        // 使用";"拆分文本
-        let texts: Vec<&str> = processed_text.split(';').collect();
+    // 合成该语言使用的音色：优先使用指定的音色，其次该语言的第一个音色
+    fn voice(&self, text_type: &TextType, name: Option<&str>) -> Result<&Voice> {
+        name.and_then(|name| self.find_voice(name))
+            .filter(|voice| voice.lang == *text_type)
+            .or_else(|| self.voices.iter().find(|voice| voice.lang == *text_type))
+            .ok_or_else(|| TTSError::Frontend(format!("no voice configured for {:?}", text_type)))
+    }
 
-        // 过滤掉空字符串
-        let result: Vec<(String, TextType)> = texts
+    pub fn split_sens(&self, text: &str) -> Vec<(String, TextType)> {
+        splitter::split(text)
             .into_iter()
-            .filter(|x| !x.is_empty() && *x != "\"")
-            .map(|text| {
-                let str_type = if text.chars().all(|c| {
-                    c.is_ascii_alphabetic() || c.is_ascii_punctuation() || c.is_whitespace()
-                }) {
-                    TextType::English
-                } else {
-                    TextType::Chinese
-                };
-                (String::from(text), str_type)
+            .map(|piece| {
+                let text_type = detect_text_type(&piece.text);
+                (piece.text, text_type)
             })
-            .collect();
+            .collect()
+    }
 
-        result
+    pub fn text2mel(&self, input_text: &str, text_type: &TextType) -> Result<Vec<f32>> {
+        let (input_ids, _) = self.voice(text_type, None)?.processor.text_to_units(input_text)?;
+        Ok(self.ids2mel(&input_ids, text_type)?.0)
     }
 
-    pub fn text2mel(&self, input_text: &str, text_type: &TextType) -> Vec<f32> {
-        let resolver = BuiltinOpResolver::default();
-        let builder: InterpreterBuilder<'_, &BuiltinOpResolver>;
-        let input_ids;
-        match text_type {
-            TextType::Chinese => {
-                input_ids = self.processor_cn.text_to_sequence(input_text, true);
-                builder = InterpreterBuilder::new(self.acoustic_model.get(0).unwrap(), &resolver)
-                    .unwrap();
-            }
-            TextType::English => {
-                input_ids = self.processor_en.text_to_sequence(input_text, true);
-                builder = InterpreterBuilder::new(self.acoustic_model.get(1).unwrap(), &resolver)
-                    .unwrap();
+    // 由输入序列生成梅尔谱，模型输出 attention 对齐时一并返回
+    pub fn ids2mel(&self, input_ids: &[i32], text_type: &TextType) -> Result<(Vec<f32>, Option<Alignment>)> {
+        let voice = self.voice(text_type, None)?;
+        let output = voice.acoustic.infer(input_ids, voice.speaker_id(None), &Prosody::default())?;
+        Ok((output.mel, output.alignment))
+    }
+
+    // 按分句生成梅尔谱与音素 id，不经过声码器；参数与合成相同，
+    // 语速只在模型能控制韵律时生效，停顿与音量不体现在梅尔谱中
+    pub fn mel_with_params(&self, text: &str, params: &SynthesisParams) -> Result<Vec<ClauseMel>> {
+        let clauses: Vec<Clause> = self
+            .clauses(text, params)?
+            .into_iter()
+            .filter(|clause| !clause.text.is_empty())
+            .collect();
+        let render = |clause: &Clause| -> Result<Option<ClauseMel>> {
+            let voice = self.voice(&clause.text_type, params.voice.as_deref())?;
+            let (input_ids, units) = voice.processor.text_to_units(&clause.text)?;
+            if units.is_empty() {
+                return Ok(None);
             }
-        }
-        // println!("input_ids: {:?}", input_ids);
+            let native = voice.acoustic.controls_prosody();
+            let prosody = Prosody {
+                speed: if native { clause.rate * params.speed * voice.defaults.speed } else { 1.0 },
+                pitch: params.pitch * voice.defaults.pitch,
+                energy: params.energy * voice.defaults.energy,
+            };
+            let speaker = voice.speaker_id(params.speaker.as_deref());
+            let output = voice.acoustic.infer(&input_ids, speaker, &prosody)?;
+            Ok(Some(ClauseMel {
+                text: clause.display.clone(),
+                lang: clause.text_type,
+                voice: voice.name.clone(),
+                input_ids,
+                mel: output.mel,
+                sample_rate: voice.vocoder.sample_rate(),
+                hop_size: voice.vocoder.hop_size(),
+            }))
+        };
+        let mels: Vec<Result<Option<ClauseMel>>> = match &self.clause_pool {
+            Some(pool) if clauses.len() > 1 => pool.install(|| clauses.par_iter().map(render).collect()),
+            _ => clauses.iter().map(render).collect(),
+        };
+        mels.into_iter().filter_map(Result::transpose).collect()
+    }
 
-        let mut interpreter = builder.build().unwrap();
-        interpreter
-            .set_shapes(0, &[1, input_ids.len() as i32])
-            .unwrap();
-        // interpreter.set_shapes(1, &[1, 1]).unwrap();
-        // interpreter.set_shapes(2, &[1]).unwrap();
+    pub fn mel2audio(&self, mel: Vec<f32>, text_type: &TextType) -> Result<Vec<f32>> {
+        self.voice(text_type, None)?.vocoder.infer(&mel)
+    }
 
-        let inputs = interpreter.inputs().to_vec();
+    pub fn synthesis(&self, text: &str, sil_time: f32) -> Result<Vec<i16>> {
+        self.synthesis_with_params(
+            text,
+            &SynthesisParams {
+                sil_time: Some(sil_time),
+                ..Default::default()
+            },
+        )
+    }
 
-        interpreter.allocate_tensors().unwrap();
+    pub fn synthesis_with_params(&self, text: &str, params: &SynthesisParams) -> Result<Vec<i16>> {
+        let mut audios = Vec::new();
+        self.synthesis_stream(text, params, |chunk| {
+            audios.extend_from_slice(&chunk);
+            true
+        })?;
+        Ok(audios)
+    }
 
-        // interpreter.print_state();
-        // interpreter.invoke().unwrap();
-        // interpreter.print_state();
+    // 分句并按参数强制指定语言
+    pub fn split_with_params(&self, text: &str, params: &SynthesisParams) -> Vec<(String, TextType)> {
+        let mut texts = self.split_sens(text);
+        if let Some(text_type) = params.text_type {
+            for text in texts.iter_mut() {
+                text.1 = text_type;
+            }
+        }
+        texts
+    }
 
-        for index in &inputs {
-            // let tmp = interpreter.tensor_info(*index).unwrap();
-            // println!("tensor{} info:{:?}", index, tmp);
-            match index {
-                0 => {
-                    // let input = vec![input_ids.clone()];
-                    interpreter
-                        .tensor_data_mut(*index)
-                        .unwrap()
-                        .copy_from_slice(&input_ids);
-                }
-                1 => {
-                    let mut input = Vec::new();
-                    input.push(input_ids.len() as i32);
-                    interpreter
-                        .tensor_data_mut(*index)
-                        .unwrap()
-                        .copy_from_slice(&input);
-                }
-                2 => {
-                    let mut input = Vec::new();
-                    input.push(1);
-                    interpreter
-                        .tensor_data_mut(*index)
-                        .unwrap()
-                        .copy_from_slice(&input);
-                }
-                _ => {}
+    // 把输入拆成待合成的分句；以 <speak> 开头的文本按 SSML 解析，
+    // <lang> 指定的语言优先于参数中的语言。没有可朗读的文本时返回 EmptyText
+    pub fn clauses(&self, text: &str, params: &SynthesisParams) -> Result<Vec<Clause>> {
+        let clauses: Vec<Clause> = if ssml::is_ssml(text) {
+            ssml::parse(text)
+                .map_err(|e| TTSError::Frontend(format!("invalid SSML: {}", e)))?
+                .into_iter()
+                .map(|segment| Clause {
+                    text_type: segment
+                        .lang
+                        .or(params.text_type)
+                        .unwrap_or_else(|| detect_text_type(&segment.text)),
+                    text: segment.text,
+                    display: segment.display,
+                    pause: segment.pause,
+                    rate: segment.rate,
+                    volume: segment.volume,
+                    boundary: segment.boundary,
+                    mood: segment.mood,
+                })
+                .collect()
+        } else {
+            splitter::split(text)
+                .into_iter()
+                .map(|piece| {
+                    let text_type = params.text_type.unwrap_or_else(|| detect_text_type(&piece.text));
+                    Clause::plain(piece.text, text_type, piece.boundary, piece.mood)
+                })
+                .collect()
+        };
+        if clauses.iter().all(|clause| clause.text.trim().is_empty()) {
+            return Err(TTSError::EmptyText);
+        }
+        let mut clauses: Vec<Clause> = clauses.into_iter().flat_map(|clause| self.split_long(clause, params)).collect();
+        // 未指定停顿的分句按前一分句之后的边界停顿，请求指定 sil_time 时统一使用该值
+        for i in 1..clauses.len() {
+            if clauses[i].pause.is_none() {
+                let pause = params.sil_time.unwrap_or_else(|| self.pauses.after(clauses[i - 1].boundary));
+                clauses[i].pause = Some(pause);
             }
         }
+        Ok(clauses)
+    }
+
+    // 超过长度上限的分句拆成多个片段，首个片段保留原有停顿，末个片段保留原有边界
+    fn split_long(&self, clause: Clause, params: &SynthesisParams) -> Vec<Clause> {
+        // 含读音标记的分句不拆，避免从标记中间断开
+        if chunk::clause_len(&clause.text) <= self.max_clause_len || self.max_clause_len == 0 || clause.text != clause.display {
+            return vec![clause];
+        }
+        let pieces = chunk::split_long(&clause.text, self.max_clause_len);
+        let last = pieces.len() - 1;
+        pieces
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| Clause {
+                display: text.clone(),
+                text,
+                pause: match i {
+                    0 => clause.pause,
+                    _ => Some(params.sil_time.map_or(CHUNK_PAUSE, |sil_time| sil_time.min(CHUNK_PAUSE))),
+                },
+                boundary: if i == last { clause.boundary } else { Boundary::Comma },
+                mood: if i == last { clause.mood } else { Mood::Statement },
+                ..clause.clone()
+            })
+            .collect()
+    }
 
-        interpreter.invoke().unwrap();
+    // 合成并返回整段音频的语音标记
+    pub fn synthesis_with_marks(&self, text: &str, params: &SynthesisParams) -> Result<(Vec<i16>, Vec<SpeechMark>)> {
+        let synthesis = self.synthesis_with_report(text, params)?;
+        Ok((synthesis.audio, synthesis.marks))
+    }
 
-        let outputs = interpreter.outputs().to_vec();
-        let output_index = outputs[1];
-        let output: &[f32] = interpreter.tensor_data(output_index).unwrap();
+    // 合成并返回音频、语音标记以及解码检查的警告
+    pub fn synthesis_with_report(&self, text: &str, params: &SynthesisParams) -> Result<Synthesis> {
+        let mut synthesis = Synthesis::default();
+        self.synthesis_stream_with_report(text, params, |chunk, marks, warnings| {
+            synthesis.audio.extend_from_slice(&chunk);
+            synthesis.marks.extend(marks);
+            synthesis.warnings.extend(warnings);
+            true
+        })?;
+        Ok(synthesis)
+    }
 
-        // let out_info = interpreter.tensor_info(output_index).unwrap();
-        // println!("tensor out: {:?}",out_info);
+    // 逐句合成：每合成完一个分句就回调 on_chunk（除首句外均带有前置静音），
+    // on_chunk 返回 false 时停止后续分句的合成；任一分句出错时停止并返回该错误
+    pub fn synthesis_stream<F>(&self, text: &str, params: &SynthesisParams, mut on_chunk: F) -> Result<()>
+    where
+        F: FnMut(Vec<i16>) -> bool,
+    {
+        self.synthesis_stream_with_marks(text, params, |chunk, _| on_chunk(chunk))
+    }
 
-        output.to_owned()
+    // 同 synthesis_stream，并附带该分句的语音标记，时间相对于整段音频的开头
+    pub fn synthesis_stream_with_marks<F>(&self, text: &str, params: &SynthesisParams, mut on_chunk: F) -> Result<()>
+    where
+        F: FnMut(Vec<i16>, Vec<SpeechMark>) -> bool,
+    {
+        self.synthesis_stream_with_report(text, params, |chunk, marks, _| on_chunk(chunk, marks))
     }
 
-    pub fn mel2audio(&self, mel: Vec<f32>, text_type: &TextType) -> Vec<f32> {
-        let resolver = BuiltinOpResolver::default();
-        let builder: InterpreterBuilder<'_, &BuiltinOpResolver>;
-        match text_type {
-            TextType::Chinese => {
-                builder =
-                    InterpreterBuilder::new(self.vocoder_model.get(0).unwrap(), &resolver).unwrap();
-            }
-            TextType::English => {
-                builder =
-                    InterpreterBuilder::new(self.vocoder_model.get(1).unwrap(), &resolver).unwrap();
+    // 同 synthesis_stream_with_marks，并附带该分句解码检查的警告
+    pub fn synthesis_stream_with_report<F>(&self, text: &str, params: &SynthesisParams, mut on_chunk: F) -> Result<()>
+    where
+        F: FnMut(Vec<i16>, Vec<SpeechMark>, Vec<DecodeWarning>) -> bool,
+    {
+        let clauses = self.clauses(text, params)?;
+        // 已输出音频的时长（毫秒），按引擎采样率计算，重采样不改变时长
+        let mut elapsed = 0;
+        // 每次同时合成 parallelism 个分句，再按顺序输出
+        for (window, start) in clauses.chunks(self.parallelism).zip((0..).step_by(self.parallelism)) {
+            for rendered in self.render_clauses(start, window, params) {
+                let (chunk, marks, duration, warnings) = rendered?;
+                let marks = marks.into_iter().map(|mark| mark.shifted(elapsed)).collect();
+                elapsed += duration;
+                if !on_chunk(chunk, marks, warnings) {
+                    return Ok(());
+                }
             }
         }
+        Ok(())
+    }
 
-        let mut interpreter = builder.build().unwrap();
-        interpreter
-            .set_shapes(0, &[1, (mel.len() / 80) as i32, 80])
-            .unwrap();
+    // 合成从第 start 个分句开始的一组分句，结果按原顺序排列
+    fn render_clauses(&self, start: usize, clauses: &[Clause], params: &SynthesisParams) -> Vec<Result<RenderedClause>> {
+        let render = |(i, clause): (usize, &Clause)| {
+            let (chunk, marks, warnings) = self.render_clause(start + i, clause, params)?;
+            let duration = to_ms(chunk.len() as f32 / self.sample_rate as f32);
+            Ok((self.to_output_rate(chunk, params)?, marks, duration, warnings))
+        };
+        match &self.clause_pool {
+            Some(pool) if clauses.len() > 1 => pool.install(|| clauses.par_iter().enumerate().map(render).collect()),
+            _ => clauses.iter().enumerate().map(render).collect(),
+        }
+    }
 
-        interpreter.allocate_tensors().unwrap();
+    // 合成第 index 个分句，含前置停顿，并转换为输出采样率；同时返回解码检查的警告
+    pub fn synthesize_clause_chunk(
+        &self,
+        index: usize,
+        clause: &Clause,
+        params: &SynthesisParams,
+    ) -> Result<(Vec<i16>, Vec<DecodeWarning>)> {
+        let (chunk, _, warnings) = self.render_clause(index, clause, params)?;
+        Ok((self.to_output_rate(chunk, params)?, warnings))
+    }
 
-        let inputs = interpreter.inputs().to_vec();
-        let input_index = inputs[0];
+    fn to_output_rate(&self, chunk: Vec<i16>, params: &SynthesisParams) -> Result<Vec<i16>> {
+        match params.sample_rate {
+            Some(sample_rate) if sample_rate != self.sample_rate => Ok(f32_to_i16(&audio::resample(
+                audio::i16_to_f32(&chunk),
+                self.sample_rate,
+                sample_rate,
+            )?)),
+            _ => Ok(chunk),
+        }
+    }
 
-        // let info = interpreter.tensor_info(input_index).unwrap();
-        // println!("tensor in: {:?}", info);
+    // 按分句的停顿、语速和音量合成，返回引擎采样率下的音频、相对于其开头的语音标记以及解码检查的警告
+    fn render_clause(
+        &self,
+        index: usize,
+        clause: &Clause,
+        params: &SynthesisParams,
+    ) -> Result<(Vec<i16>, Vec<SpeechMark>, Vec<DecodeWarning>)> {
+        let pause = match clause.pause {
+            Some(pause) => pause,
+            None if index > 0 => params.sil_time.unwrap_or(self.pauses.comma),
+            None => 0.0,
+        };
+        let mut chunk = vec![0; (pause * self.sample_rate as f32) as usize];
+        // 文档末尾的停顿没有文本
+        if clause.text.is_empty() {
+            return Ok((chunk, Vec::new(), Vec::new()));
+        }
 
-        interpreter
-            .tensor_data_mut(input_index)
-            .unwrap()
-            .copy_from_slice(&mel);
-        interpreter.invoke().unwrap();
+        let start = to_ms(chunk.len() as f32 / self.sample_rate as f32);
+        // 模型能控制语速时直接合成目标语速，否则合成后做时间伸缩
+        let voice = self.voice(&clause.text_type, params.voice.as_deref())?;
+        let speed = clause.rate * params.speed * voice.defaults.speed;
+        let native = voice.acoustic.controls_prosody();
+        let prosody = Prosody {
+            speed: if native { speed } else { 1.0 },
+            pitch: params.pitch * voice.defaults.pitch,
+            energy: params.energy * voice.defaults.energy,
+        };
+        let (mut samples, clause_marks, issues) =
+            self.synthesize_checked(&clause.text, voice, params.speaker.as_deref(), &prosody, 0)?;
+        let stretch = if native { 1.0 } else { speed };
+        if stretch != 1.0 {
+            samples = audio::time_stretch(&samples, stretch, self.sample_rate);
+        }
+        if clause.volume != 1.0 {
+            audio::apply_gain(&mut samples, clause.volume);
+        }
+        chunk.extend_from_slice(&samples);
 
-        let outputs = interpreter.outputs().to_vec();
-        let output_index = outputs[0];
-        let output: &[f32] = interpreter.tensor_data(output_index).unwrap();
+        let mut marks = vec![SpeechMark {
+            mark_type: MarkType::Sentence,
+            value: clause.display.clone(),
+            start_ms: start,
+            end_ms: to_ms(chunk.len() as f32 / self.sample_rate as f32),
+        }];
+        marks.extend(
+            clause_marks
+                .into_iter()
+                .map(|mark| mark.scaled(1.0 / stretch).shifted(start)),
+        );
+        let warnings = issues
+            .into_iter()
+            .map(|(text, issue)| DecodeWarning { clause: index, text, issue })
+            .collect();
+        Ok((chunk, marks, warnings))
+    }
 
-        // let out_info = interpreter.tensor_info(output_index).unwrap();
-        // println!("tensor out: {:?}",out_info);
+    // 合成单个分句，返回引擎采样率下的音频
+    pub fn synthesize_clause(&self, text: &str, text_type: TextType) -> Result<Vec<i16>> {
+        Ok(self.synthesize_clause_with_marks(text, text_type)?.0)
+    }
 
-        output.to_owned()
+    // 合成单个分句，并按 attention 对齐给出每个字/词的时间，时间相对于分句开头；
+    // 没有可朗读内容（如只有无法识别的符号）的分句返回空音频
+    pub fn synthesize_clause_with_marks(&self, text: &str, text_type: TextType) -> Result<(Vec<i16>, Vec<SpeechMark>)> {
+        let (audio, marks, _) = self.synthesize_with_voice(text, self.voice(&text_type, None)?, None, &Prosody::default())?;
+        Ok((audio, marks))
     }
 
-    pub fn synthesis(&self, text: &str, sil_time: f32) -> Vec<i16> {
-        let mut audios = Vec::new();
-        let texts = self.split_sens(text);
-        let silence = vec![0; (sil_time * self.sample_rate as f32) as usize];
+    // 合成单个分句并检查自回归模型的解码结果；未通过检查时在中间附近的边界拆成两半分别重试，
+    // 无法再拆时保留结果并返回未通过检查的文本
+    fn synthesize_checked(
+        &self,
+        text: &str,
+        voice: &Voice,
+        speaker: Option<&str>,
+        prosody: &Prosody,
+        depth: usize,
+    ) -> Result<CheckedAudio> {
+        let (audio, marks, issue) = self.synthesize_with_voice(text, voice, speaker, prosody)?;
+        let Some(issue) = issue else {
+            return Ok((audio, marks, Vec::new()));
+        };
+        let Some((first, second)) = chunk::split_half(text).filter(|_| depth < MAX_RETRY_DEPTH) else {
+            warn!("voice {} failed to decode {:?}: {:?}", voice.name, text, issue);
+            return Ok((audio, marks, vec![(text.to_string(), issue)]));
+        };
+        warn!("voice {} failed to decode {:?}: {:?}, retrying in two parts", voice.name, text, issue);
+        let (mut audio, mut marks, mut issues) = self.synthesize_checked(&first, voice, speaker, prosody, depth + 1)?;
+        let offset = to_ms(audio.len() as f32 / self.sample_rate as f32);
+        let (rest, rest_marks, rest_issues) = self.synthesize_checked(&second, voice, speaker, prosody, depth + 1)?;
+        audio.extend_from_slice(&rest);
+        marks.extend(rest_marks.into_iter().map(|mark| mark.shifted(offset)));
+        issues.extend(rest_issues);
+        Ok((audio, marks, issues))
+    }
 
-        for (i, text) in texts.iter().enumerate() {
-            let a16: Vec<i16>;
-            match text.1.to_owned() {
-                TextType::Chinese => {
-                    let mel = self.text2mel(text.0.as_str(), &TextType::Chinese);
-                    let audio = self.mel2audio(mel, &TextType::Chinese);
-                    a16 = audio
-                        .iter()
-                        .map(|&x| (x * i16::MAX as f32) as i16)
-                        .collect();
-                }
-                TextType::English => {
-                    let mel = self.text2mel(text.0.as_str(), &TextType::English);
-                    let audio = self.mel2audio(mel, &TextType::English);
-                    let params = SincInterpolationParameters {
-                        sinc_len: 256,
-                        f_cutoff: 0.95,
-                        interpolation: SincInterpolationType::Linear,
-                        oversampling_factor: 256,
-                        window: WindowFunction::BlackmanHarris2,
-                    };
-                    let mut resampler = SincFixedIn::<f32>::new(
-                        24000 as f64 / 22050 as f64,
-                        2.0,
-                        params,
-                        audio.len(),
-                        1,
-                    )
-                    .unwrap();
-                    let converted_data: Vec<Vec<f32>> = vec![audio; 1];
-                    let res_audio = resampler.process(&converted_data, None).unwrap();
-                    a16 = res_audio
-                        .into_iter()
-                        .flat_map(|x| x.into_iter().map(|a| (a * i16::MAX as f32) as i16))
-                        .collect();
-                    // a16 = audio
-                    //     .iter()
-                    //     .map(|&x| (x * i16::MAX as f32) as i16)
-                    //     .collect();
-                }
-            }
+    // 用指定音色、说话人与韵律合成单个分句；模型不支持的韵律控制被忽略。
+    // 自回归模型同时返回解码检查的结果
+    fn synthesize_with_voice(
+        &self,
+        text: &str,
+        voice: &Voice,
+        speaker: Option<&str>,
+        prosody: &Prosody,
+    ) -> Result<(Vec<i16>, Vec<SpeechMark>, Option<DecodeIssue>)> {
+        let (input_ids, units) = voice.processor.text_to_units(text)?;
+        if units.is_empty() {
+            return Ok((Vec::new(), Vec::new(), None));
+        }
+        let output = voice.acoustic.infer(&input_ids, voice.speaker_id(speaker), prosody)?;
+        let alignment = output.alignment;
+        let audio = voice.vocoder.infer(&output.mel)?;
+        // 声码器输出的时长，对齐的解码步均匀分布在其中
+        let vocoder_rate = voice.vocoder.sample_rate();
+        let issue = match voice.acoustic.autoregressive() {
+            true => check_decode(input_ids.len(), output.mel.len() / NUM_MELS, &audio, vocoder_rate),
+            false => None,
+        };
+        let model_duration = audio.len() as f32 / vocoder_rate as f32;
 
-            if self.text2mel_name == "TACOTRON" {
-                // tacotron will generate noise at the end
-                audios.extend_from_slice(&a16[..a16.len() - 2048]);
-            } else {
-                audios.extend_from_slice(&a16);
-            }
+        let mut a16: Vec<i16> = match vocoder_rate == self.sample_rate {
+            true => f32_to_i16(&audio),
+            false => f32_to_i16(&audio::resample(audio, vocoder_rate, self.sample_rate)?),
+        };
 
-            if i < texts.len() - 1 {
-                audios.extend_from_slice(&silence);
-            }
-        }
+        // 如 Tacotron2 末尾的噪声，过短的分句整段丢弃
+        a16.truncate(a16.len().saturating_sub(voice.acoustic.tail_samples()));
+
+        let duration = a16.len() as f32 / self.sample_rate as f32;
+        let marks = alignment
+            .map(|alignment| {
+                let step_sec = model_duration / alignment.steps() as f32;
+                unit_marks(&units, &alignment.token_boundaries(), step_sec, duration)
+            })
+            .unwrap_or_default();
 
-        audios
+        Ok((a16, marks, issue))
     }
 }
 
@@ -284,7 +650,7 @@ mod tests {
     #[test]
     fn test_split_sens() {
         println!("test_split_sens :{:?}", env::current_dir());
-        let engine = TTSEngine::new();
+        let engine = TTSEngine::new().unwrap();
         let result = engine.split_sens("今天天气不错，有50%的概率会下雨！");
         println!("{:?}", result);
         let result = engine.split_sens("english test.");
@@ -298,8 +664,8 @@ mod tests {
     #[test]
     fn test_synthesis() {
         println!("test_synthesis");
-        let engine = TTSEngine::new();
-        let wav = engine.synthesis("今天天气不错,有50%的概率会下雨！", 0.2);
+        let engine = TTSEngine::new().unwrap();
+        let wav = engine.synthesis("今天天气不错,有50%的概率会下雨！", 0.2).unwrap();
         let mut writer = WavWriter::create(
             "tts.wav",
             WavSpec {
@@ -317,11 +683,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parallel_synthesis() {
+        let text = "今天天气不错，有50%的概率会下雨，请记得带伞。The price is 123 dollars.";
+        let sequential = TTSEngine::new().unwrap().synthesis(text, 0.2).unwrap();
+        let engine = TTSEngine::with_options(&EngineOptions {
+            parallelism: 4,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(engine.synthesis(text, 0.2).unwrap(), sequential);
+    }
+
     #[test]
     fn test_synthesis1() {
         println!("test_synthesis");
-        let engine = TTSEngine::new();
-        let wav = engine.synthesis("倒车，请注意", 0.2);
+        let engine = TTSEngine::new().unwrap();
+        let wav = engine.synthesis("倒车，请注意", 0.2).unwrap();
         let mut writer = WavWriter::create(
             "tts1.wav",
             WavSpec {
@@ -342,8 +720,8 @@ mod tests {
     #[test]
     fn test_synthesis_en() {
         println!("test_synthesis_en");
-        let engine = TTSEngine::new();
-        let wav = engine.synthesis("Printing, in the only sense with which we are at present concerned, differs from most if not from all the arts and crafts represented in the Exhibition in being comparatively modern.", 0.2);
+        let engine = TTSEngine::new().unwrap();
+        let wav = engine.synthesis("Printing, in the only sense with which we are at present concerned, differs from most if not from all the arts and crafts represented in the Exhibition in being comparatively modern.", 0.2).unwrap();
         let mut writer = WavWriter::create(
             "tts2.wav",
             WavSpec {
@@ -364,8 +742,8 @@ mod tests {
     #[test]
     fn test_synthesis_cnen() {
         println!("test_synthesis_en");
-        let engine = TTSEngine::new();
-        let wav = engine.synthesis("乘客朋友，您好，请您坐稳扶好，酷哇科技无人驾驶小巴即将带您体验和参观“无人之境”项目。“无人之境”示范体验区是国家智能网联汽车（上海）试点示范区的重要组成部分，可支撑无人化高级别自动驾驶技术测试验证。目前已实现无人驾驶小巴， robot taxi、无人清扫等多业态无人驾驶应用场景。此时此刻在您右侧的建筑是上海汽车博物馆。他是中国首家以汽车为主题，融汽车历史、人物、技术和创意为一体的专业博物馆。博览馆建筑优美流程，收藏了近百台世界各地的古董车。本车由上海汽车博物馆站，开往一维诶爱智行港终点站，下一站，房车中国上海基地站，车辆离站，请系好安全带", 0.1);
+        let engine = TTSEngine::new().unwrap();
+        let wav = engine.synthesis("乘客朋友，您好，请您坐稳扶好，酷哇科技无人驾驶小巴即将带您体验和参观“无人之境”项目。“无人之境”示范体验区是国家智能网联汽车（上海）试点示范区的重要组成部分，可支撑无人化高级别自动驾驶技术测试验证。目前已实现无人驾驶小巴， robot taxi、无人清扫等多业态无人驾驶应用场景。此时此刻在您右侧的建筑是上海汽车博物馆。他是中国首家以汽车为主题，融汽车历史、人物、技术和创意为一体的专业博物馆。博览馆建筑优美流程，收藏了近百台世界各地的古董车。本车由上海汽车博物馆站，开往一维诶爱智行港终点站，下一站，房车中国上海基地站，车辆离站，请系好安全带", 0.1).unwrap();
         let mut writer = WavWriter::create(
             "tts3.wav",
             WavSpec {
@@ -3754,8 +4132,8 @@ mod tests {
 
         let mel1: Vec<f32> = mel.into_iter().flatten().flatten().collect();
         println!("mel1: {:?}", mel1.len());
-        let engine = TTSEngine::new();
-        let wav = engine.mel2audio(mel1, &TextType::Chinese);
+        let engine = TTSEngine::new().unwrap();
+        let wav = engine.mel2audio(mel1, &TextType::Chinese).unwrap();
         let mut writer = hound::WavWriter::create(
             "tts.wav",
             hound::WavSpec {
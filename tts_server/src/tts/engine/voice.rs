@@ -0,0 +1,331 @@
+use super::baker::BakerProcessor;
+use super::error::{Result, TTSError};
+use super::fastspeech2::FastSpeech2;
+use super::griffin_lim::{GriffinLim, GriffinLimConfig};
+use super::interpreter::InterpreterPool;
+use super::ljspeech::LJSpeechProcessor;
+use super::mb_melgan::MbMelGan;
+use super::model::{AcousticModel, TextProcessor, Vocoder};
+use super::tacotron2::Tacotron2;
+use super::tts_engine::{EngineOptions, TextType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+// 文本前端的类型，决定音素表与文本规范化方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessorKind {
+    Baker,
+    LJSpeech,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AcousticConfig {
+    Tacotron2 { model: String },
+    #[serde(rename = "fastspeech2")]
+    FastSpeech2 { model: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VocoderConfig {
+    MbMelgan {
+        model: String,
+        sample_rate: usize,
+        /// 每帧梅尔谱对应的采样数，不填时按 TensorFlowTTS 的配置由采样率推断
+        #[serde(default)]
+        hop_size: Option<usize>,
+    },
+    GriffinLim(GriffinLimConfig),
+}
+
+// 未配置声码器时使用不依赖模型的 Griffin-Lim
+impl Default for VocoderConfig {
+    fn default() -> Self {
+        VocoderConfig::GriffinLim(GriffinLimConfig::default())
+    }
+}
+
+impl VocoderConfig {
+    // 声码器输出（即音色原生）的采样率
+    pub fn sample_rate(&self) -> usize {
+        match self {
+            VocoderConfig::MbMelgan { sample_rate, .. } => *sample_rate,
+            VocoderConfig::GriffinLim(config) => config.sample_rate,
+        }
+    }
+
+    // 每帧梅尔谱对应的采样数（按声码器采样率计算）
+    pub fn hop_size(&self) -> usize {
+        match self {
+            VocoderConfig::MbMelgan { hop_size: Some(hop_size), .. } => *hop_size,
+            // TensorFlowTTS 的 LJSpeech（22050Hz）为 256，标贝（24000Hz）为 300
+            VocoderConfig::MbMelgan { sample_rate: 22050, .. } => 256,
+            VocoderConfig::MbMelgan { sample_rate, .. } => sample_rate / 80,
+            VocoderConfig::GriffinLim(config) => config.hop_size,
+        }
+    }
+}
+
+// 音色的默认参数，语速、音高、能量与请求中的倍率相乘
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+#[serde(default)]
+pub struct VoiceDefaults {
+    /// 默认说话人，不填时使用 id 最小的说话人
+    pub speaker: Option<String>,
+    pub speed: f32,
+    pub pitch: f32,
+    pub energy: f32,
+}
+
+impl Default for VoiceDefaults {
+    fn default() -> Self {
+        Self {
+            speaker: None,
+            speed: 1.0,
+            pitch: 1.0,
+            energy: 1.0,
+        }
+    }
+}
+
+// 一个音色由文本前端、声学模型和声码器组成；相对路径按 model_dir 解析
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceConfig {
+    pub name: String,
+    pub lang: TextType,
+    pub processor: ProcessorKind,
+    /// 音素表与说话人所在的 mapper 文件
+    pub mapper: String,
+    pub acoustic: AcousticConfig,
+    #[serde(default)]
+    pub vocoder: VocoderConfig,
+    #[serde(default)]
+    pub defaults: VoiceDefaults,
+}
+
+impl VoiceConfig {
+    // 音色引用的文件：mapper、声学模型，以及声码器模型或 Griffin-Lim 的 stats 文件
+    pub fn paths_mut(&mut self) -> Vec<&mut String> {
+        let acoustic = match &mut self.acoustic {
+            AcousticConfig::Tacotron2 { model } | AcousticConfig::FastSpeech2 { model } => model,
+        };
+        let vocoder = match &mut self.vocoder {
+            VocoderConfig::MbMelgan { model, .. } => Some(model),
+            VocoderConfig::GriffinLim(config) => config.stats.as_mut(),
+        };
+        [&mut self.mapper, acoustic].into_iter().chain(vocoder).collect()
+    }
+
+    // 内置音色：标贝中文与 LJSpeech 英文
+    pub fn builtin() -> Vec<VoiceConfig> {
+        vec![
+            VoiceConfig {
+                name: "baker".to_string(),
+                lang: TextType::Chinese,
+                processor: ProcessorKind::Baker,
+                mapper: "assets/baker_mapper.json".to_string(),
+                acoustic: AcousticConfig::Tacotron2 {
+                    model: "assets/tacotron2.baker_quan.tflite".to_string(),
+                },
+                vocoder: VocoderConfig::MbMelgan {
+                    model: "assets/mb_melgan.baker.tflite".to_string(),
+                    sample_rate: 24000,
+                    hop_size: Some(300),
+                },
+                defaults: VoiceDefaults::default(),
+            },
+            VoiceConfig {
+                name: "ljspeech".to_string(),
+                lang: TextType::English,
+                processor: ProcessorKind::LJSpeech,
+                mapper: "assets/ljspeech_mapper.json".to_string(),
+                acoustic: AcousticConfig::Tacotron2 {
+                    model: "assets/tacotron2.ljspeech_quan.tflite".to_string(),
+                },
+                vocoder: VocoderConfig::MbMelgan {
+                    model: "assets/mb_melgan.ljspeech.tflite".to_string(),
+                    sample_rate: 22050,
+                    hop_size: Some(256),
+                },
+                defaults: VoiceDefaults::default(),
+            },
+        ]
+    }
+}
+
+// 加载完成的音色
+pub struct Voice {
+    pub name: String,
+    pub lang: TextType,
+    pub processor: Box<dyn TextProcessor>,
+    pub acoustic: Box<dyn AcousticModel>,
+    pub vocoder: Box<dyn Vocoder>,
+    /// 说话人名称与 id，按 id 排列
+    pub speakers: Vec<(String, i32)>,
+    pub defaults: VoiceDefaults,
+}
+
+impl Voice {
+    pub fn load(config: &VoiceConfig, options: &EngineOptions) -> Result<Voice> {
+        let load_error = |e: std::io::Error| TTSError::ModelLoad(format!("voice {}: {}", config.name, e));
+        let resolve = |path: &str| resolve_path(options.model_dir.as_deref(), path);
+        let mapper = resolve(&config.mapper);
+        let processor: Box<dyn TextProcessor> = match config.processor {
+            ProcessorKind::Baker => Box::new(BakerProcessor::with_mapper(&mapper).map_err(load_error)?),
+            ProcessorKind::LJSpeech => Box::new(LJSpeechProcessor::with_mapper(&mapper).map_err(load_error)?),
+        };
+        let load_model = |model: &str| InterpreterPool::new(&resolve(model), options.interpreters, options.threads);
+        let acoustic: Box<dyn AcousticModel> = match &config.acoustic {
+            AcousticConfig::Tacotron2 { model } => Box::new(Tacotron2::new(load_model(model)?)?),
+            AcousticConfig::FastSpeech2 { model } => Box::new(FastSpeech2::new(load_model(model)?)?),
+        };
+        let vocoder: Box<dyn Vocoder> = match &config.vocoder {
+            VocoderConfig::MbMelgan { model, sample_rate, .. } => {
+                Box::new(MbMelGan::new(load_model(model)?, *sample_rate, config.vocoder.hop_size()))
+            }
+            VocoderConfig::GriffinLim(config) => Box::new(GriffinLim::new(GriffinLimConfig {
+                stats: config.stats.as_deref().map(resolve),
+                ..config.clone()
+            })?),
+        };
+        let speakers = speaker_list(&config.name, processor.speakers(), acoustic.num_speakers())?;
+        let defaults = &config.defaults;
+        if let Some(speaker) = defaults.speaker.as_deref().filter(|s| !speakers.iter().any(|(name, _)| name == s)) {
+            return Err(TTSError::ModelLoad(format!("voice {}: unknown default speaker {}", config.name, speaker)));
+        }
+        if [defaults.speed, defaults.pitch, defaults.energy].iter().any(|ratio| !ratio.is_finite() || *ratio <= 0.0) {
+            return Err(TTSError::ModelLoad(format!("voice {}: default ratios must be positive", config.name)));
+        }
+        Ok(Voice {
+            name: config.name.clone(),
+            lang: config.lang,
+            processor,
+            acoustic,
+            vocoder,
+            speakers,
+            defaults: defaults.clone(),
+        })
+    }
+
+    pub fn has_speaker(&self, name: &str) -> bool {
+        self.speakers.iter().any(|(speaker, _)| speaker == name)
+    }
+
+    // 送入模型的说话人 id：指定的说话人不属于该音色时使用默认说话人
+    pub fn speaker_id(&self, name: Option<&str>) -> i32 {
+        resolve_speaker(&self.speakers, self.defaults.speaker.as_deref(), name, self.acoustic.num_speakers())
+    }
+}
+
+// 没有说话人嵌入表的模型（如内置的标贝与 LJSpeech）沿用旧版引擎固定传入的 id，
+// 不按 mapper 中的 id 改变，保证已有部署的输出不变
+const SINGLE_SPEAKER_ID: i32 = 1;
+
+fn resolve_speaker(speakers: &[(String, i32)], default: Option<&str>, name: Option<&str>, num_speakers: Option<usize>) -> i32 {
+    if num_speakers.is_none() {
+        return SINGLE_SPEAKER_ID;
+    }
+    let find = |name: &str| speakers.iter().find(|(speaker, _)| speaker == name);
+    name.and_then(find)
+        .or_else(|| default.and_then(find))
+        .or(speakers.first())
+        .map_or(0, |(_, id)| *id)
+}
+
+// 相对路径按 model_dir 解析，未配置 model_dir 时按工作目录解析
+fn resolve_path(model_dir: Option<&Path>, path: &str) -> String {
+    match model_dir {
+        Some(dir) if Path::new(path).is_relative() => dir.join(path).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+// 按 id 排列 mapper 中的说话人，并检查 id 不超出模型的说话人嵌入表；
+// 单说话人模型没有嵌入表，只接受 id 0
+fn speaker_list(voice: &str, speakers_map: &HashMap<String, usize>, num_speakers: Option<usize>) -> Result<Vec<(String, i32)>> {
+    let limit = num_speakers.unwrap_or(1);
+    let mut speakers = speakers_map
+        .iter()
+        .map(|(name, &id)| match id < limit {
+            true => Ok((name.clone(), id as i32)),
+            false => Err(TTSError::ModelLoad(format!(
+                "voice {}: speaker {} has id {} but the model has {} speaker(s)",
+                voice, name, id, limit
+            ))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    speakers.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(speakers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speaker_list() {
+        let map = HashMap::from([("b".to_string(), 1), ("a".to_string(), 0)]);
+        assert_eq!(
+            speaker_list("v", &map, Some(2)).unwrap(),
+            vec![("a".to_string(), 0), ("b".to_string(), 1)]
+        );
+        assert!(speaker_list("v", &map, Some(1)).is_err());
+        assert!(speaker_list("v", &map, None).is_err());
+        assert_eq!(speaker_list("v", &HashMap::new(), None).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_resolve_speaker() {
+        // 内置音色的 mapper 只有 id 为 0 的说话人，模型没有嵌入表，仍传入旧版的 1
+        let builtin = speaker_list("baker", &HashMap::from([("baker".to_string(), 0)]), None).unwrap();
+        assert_eq!(resolve_speaker(&builtin, None, None, None), 1);
+        assert_eq!(resolve_speaker(&builtin, None, Some("baker"), None), 1);
+
+        let speakers = vec![("a".to_string(), 0), ("b".to_string(), 1), ("c".to_string(), 2)];
+        assert_eq!(resolve_speaker(&speakers, None, None, Some(3)), 0);
+        assert_eq!(resolve_speaker(&speakers, Some("c"), None, Some(3)), 2);
+        assert_eq!(resolve_speaker(&speakers, Some("c"), Some("b"), Some(3)), 1);
+        assert_eq!(resolve_speaker(&speakers, Some("c"), Some("x"), Some(3)), 2);
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(resolve_path(None, "assets/a.tflite"), "assets/a.tflite");
+        assert_eq!(resolve_path(Some(Path::new("/models")), "assets/a.tflite"), "/models/assets/a.tflite");
+        assert_eq!(resolve_path(Some(Path::new("/models")), "/data/a.tflite"), "/data/a.tflite");
+    }
+
+    #[test]
+    fn test_voice_config() {
+        let yaml = r#"
+name: xiaoyu
+lang: Chinese
+processor: baker
+mapper: baker_mapper.json
+acoustic: { type: fastspeech2, model: fastspeech2.baker.tflite }
+vocoder: { type: mb_melgan, model: mb_melgan.baker.tflite, sample_rate: 24000 }
+defaults: { speed: 1.1 }
+"#;
+        let config: VoiceConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.processor, ProcessorKind::Baker);
+        assert_eq!(config.acoustic, AcousticConfig::FastSpeech2 { model: "fastspeech2.baker.tflite".to_string() });
+        assert_eq!(config.vocoder.sample_rate(), 24000);
+        assert_eq!(config.vocoder.hop_size(), 300);
+        assert_eq!(config.defaults, VoiceDefaults { speed: 1.1, ..Default::default() });
+
+        // 不配置声码器时使用 Griffin-Lim
+        let yaml = yaml.replace("vocoder: { type: mb_melgan, model: mb_melgan.baker.tflite, sample_rate: 24000 }\n", "");
+        let mut config: VoiceConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config.vocoder, VocoderConfig::default());
+        assert_eq!(config.paths_mut().len(), 2);
+
+        let yaml = "type: griffin_lim\nsample_rate: 22050\nfft_size: 1024\nhop_size: 256\nwin_length: 1024\nstats: stats.npy\n";
+        let vocoder: VocoderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(vocoder.sample_rate(), 22050);
+        let VocoderConfig::GriffinLim(config) = vocoder else { panic!("{:?}", vocoder) };
+        assert_eq!((config.iterations, config.stats.as_deref()), (32, Some("stats.npy")));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod server;
 pub mod api;
-pub mod engine;
\ No newline at end of file
+pub mod engine;
+pub mod reload;
\ No newline at end of file
@@ -0,0 +1,162 @@
+// 音色与模型的热加载：重新读取配置，在后台构建新引擎并试合成，
+// 成功后原子替换；进行中的请求继续使用旧引擎，失败时旧引擎照常服务
+#[cfg(unix)]
+use actix_web::web;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{error, info, warn};
+use super::super::base::configuration::{self, AppConfigItem};
+use super::super::error::AppError;
+use super::super::AppState;
+use super::engine::bundle;
+use super::engine::chunk::DEFAULT_MAX_CLAUSE_LEN;
+use super::engine::error::{Result, TTSError};
+use super::engine::tts_engine::{EngineOptions, SynthesisParams, TTSEngine, TextType};
+use super::engine::voice::VoiceConfig;
+
+// 按配置加载音色并构建引擎；每个合成线程各有一组解释器，默认按合成线程数平分 CPU 核用于分句并行
+pub fn build_engine(config: &AppConfigItem, workers: usize) -> Result<TTSEngine> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut voices = match (&config.voices, &config.voices_dir) {
+        (Some(voices), _) => voices.clone(),
+        (None, Some(_)) => Vec::new(),
+        (None, None) => VoiceConfig::builtin(),
+    };
+    if let Some(dir) = &config.voices_dir {
+        for bundle in bundle::load_dir(dir)? {
+            let manifest = bundle.manifest;
+            info!("voice bundle {}: {} {} ({})", bundle.path.display(), manifest.voice.name, manifest.version, manifest.licence);
+            voices.push(manifest.voice);
+        }
+    }
+    let engine = TTSEngine::with_options(&EngineOptions {
+        interpreters: workers,
+        threads: config.interpreter_threads.unwrap_or(1),
+        parallelism: config.clause_parallelism.unwrap_or(cores / workers).max(1),
+        voices,
+        model_dir: config.model_dir.clone(),
+        max_clause_len: config.max_clause_len.unwrap_or(DEFAULT_MAX_CLAUSE_LEN),
+        pauses: config.pauses.unwrap_or_default(),
+    })?;
+    for voice in engine.voices() {
+        info!("voice {}: {:?}, {} speaker(s)", voice.name, voice.lang, voice.speakers.len());
+    }
+    Ok(engine)
+}
+
+// 用每个音色试合成一句，确认模型与 mapper 可用
+pub fn validate(engine: &TTSEngine) -> Result<()> {
+    for voice in engine.voices() {
+        let text = match voice.lang {
+            TextType::Chinese => "你好。",
+            TextType::English => "Hello.",
+        };
+        let params = SynthesisParams {
+            text_type: Some(voice.lang),
+            voice: Some(voice.name.clone()),
+            ..Default::default()
+        };
+        let audio = engine
+            .synthesis_with_params(text, &params)
+            .map_err(|e| TTSError::ModelLoad(format!("voice {} failed test synthesis: {}", voice.name, e)))?;
+        if audio.is_empty() {
+            return Err(TTSError::ModelLoad(format!("voice {} produced no audio", voice.name)));
+        }
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ReloadResponse {
+    /// 新引擎加载的音色
+    pub voices: Vec<String>,
+    /// 加载与试合成的耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+// 重新加载所需的配置；合成线程数与队列等在启动后不再改变
+pub struct Reloader {
+    config_file: PathBuf,
+    workers: usize,
+    admin_key: Option<String>,
+    running: Mutex<()>,
+}
+
+impl Reloader {
+    pub fn new(config_file: PathBuf, workers: usize, admin_key: Option<String>) -> Self {
+        Self {
+            config_file,
+            workers,
+            admin_key,
+            running: Mutex::new(()),
+        }
+    }
+
+    // 校验管理接口的密钥，未配置密钥时拒绝所有请求
+    pub fn authorize(&self, key: Option<&str>) -> std::result::Result<(), AppError> {
+        match (&self.admin_key, key) {
+            (None, _) => Err(AppError::Forbidden("admin endpoints are disabled".to_string())),
+            (Some(expected), Some(key)) if expected == key => Ok(()),
+            _ => Err(AppError::Forbidden("invalid admin key".to_string())),
+        }
+    }
+
+    // 重新读取配置并加载音色，试合成通过后替换引擎；同一时间只允许一次重新加载
+    pub fn reload(&self, data: &AppState) -> std::result::Result<ReloadResponse, AppError> {
+        let _running = self
+            .running
+            .try_lock()
+            .map_err(|_| AppError::Conflict("reload already in progress".to_string()))?;
+        let start = Instant::now();
+        let result = configuration::decode_config(&self.config_file)
+            .map_err(|e| AppError::Internal(format!("{}: {}", self.config_file.display(), e)))
+            .and_then(|config| {
+                let engine = build_engine(&config, self.workers)?;
+                validate(&engine)?;
+                Ok(engine)
+            });
+        let engine = result.inspect_err(|e| error!("reload failed, keep serving the old voices: {}", e))?;
+
+        let voices = engine.voices().iter().map(|voice| voice.name.clone()).collect();
+        data.swap_engine(engine);
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        info!("reloaded voices {:?} in {}ms", voices, elapsed_ms);
+        Ok(ReloadResponse { voices, elapsed_ms })
+    }
+}
+
+// 收到 SIGHUP 时重新加载
+#[cfg(unix)]
+pub async fn reload_on_hangup(data: web::Data<AppState>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            warn!("failed to listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        info!("SIGHUP received, reloading voices");
+        let state = data.clone();
+        // 结果已在 reload 中记录
+        let _ = web::block(move || state.reloader.reload(&state)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorize() {
+        let disabled = Reloader::new(PathBuf::from("config/tts.yaml"), 1, None);
+        assert!(matches!(disabled.authorize(Some("secret")), Err(AppError::Forbidden(_))));
+
+        let reloader = Reloader::new(PathBuf::from("config/tts.yaml"), 1, Some("secret".to_string()));
+        assert!(reloader.authorize(Some("secret")).is_ok());
+        assert!(reloader.authorize(Some("wrong")).is_err());
+        assert!(reloader.authorize(None).is_err());
+    }
+}
@@ -1,10 +1,19 @@
 use actix_web::*;
 use actix_files as fs;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use super::api::{tts_handler, index};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use super::api::{tts_handler, stream_handler, marks_handler, mel_handler, batch_handler, job_handler, ws_handler, voices_handler, admin_handler, index};
 use super::super::base::configuration::AppConfigItem;
-use super::engine::tts_engine::TTSEngine;
+use super::super::base::job::{JobStatus, JobStore, JOBS_DIR};
+use super::super::base::pool::{Priority, WorkerPool};
+use super::super::error::ErrorResponse;
+use super::engine::audio::AudioFormat;
+use super::engine::mel::MelFormat;
+use super::engine::quality::{DecodeIssue, DecodeWarning};
+use super::engine::timing::{MarkType, MarksFormat, SpeechMark};
+use super::engine::tts_engine::TextType;
+use super::engine::voice::VoiceDefaults;
+use super::reload::{self, Reloader, ReloadResponse};
 use super::super::{AppState, QueryTracker};
 use tracing::{self, info};
 use chrono::{Local, Datelike, Timelike};
@@ -16,11 +25,52 @@ use utoipa_swagger_ui::SwaggerUi;
 #[openapi(
     paths(
         tts_handler::api_tts,
+        tts_handler::api_tts_post,
+        stream_handler::api_tts_stream,
+        marks_handler::api_tts_marks,
+        mel_handler::api_tts_mel,
+        batch_handler::api_tts_batch,
+        job_handler::api_job_submit,
+        job_handler::api_job_status,
+        job_handler::api_job_audio,
+        job_handler::api_job_delete,
+        ws_handler::ws_tts,
+        voices_handler::api_voices,
+        admin_handler::api_admin_reload,
         index::index,
     ),
     components(
         schemas(
             tts_handler::TTSQuery,
+            tts_handler::TTSRequest,
+            marks_handler::MarksRequest,
+            marks_handler::MarksResponse,
+            mel_handler::MelRequest,
+            mel_handler::MelResponse,
+            mel_handler::MelClause,
+            batch_handler::BatchRequest,
+            batch_handler::BatchItem,
+            batch_handler::BatchReport,
+            batch_handler::BatchItemReport,
+            job_handler::JobRequest,
+            job_handler::JobResponse,
+            JobStatus,
+            Priority,
+            SpeechMark,
+            MarkType,
+            MarksFormat,
+            MelFormat,
+            DecodeWarning,
+            DecodeIssue,
+            ws_handler::WsRequest,
+            ws_handler::WsEvent,
+            voices_handler::VoiceInfo,
+            voices_handler::SpeakerInfo,
+            VoiceDefaults,
+            ReloadResponse,
+            AudioFormat,
+            TextType,
+            ErrorResponse,
         ),
     ),
     tags(
@@ -30,21 +80,51 @@ use utoipa_swagger_ui::SwaggerUi;
 struct ApiDoc;
 
 #[actix_web::main]
-pub async fn start(config: &AppConfigItem) -> anyhow::Result<()> {
+pub async fn start(config_file: &Path, config: &AppConfigItem) -> anyhow::Result<()> {
 
     let now = Local::now();
     let nowtime = format!("{:02}/{:02}/{:04} {:02}:{:02}:{:02}", now.month(), now.day(), now.year(), now.hour(), now.minute(), now.second());
     info!("tts_server start at {}.", nowtime);
 
-    let app_state = web::Data::new(Arc::new(RwLock::new(AppState {
-        engine: TTSEngine::new(),
-        track: QueryTracker::new(nowtime),
-    })));
+    let (jobs, job_queue) = JobStore::open(JOBS_DIR)?;
+    let jobs = Arc::new(jobs);
+    let pool = WorkerPool::new(config.workers, config.queue_size, config.retry_after, config.bulk_share);
+    info!("synthesis workers: {}, queue size: {}", pool.workers(), pool.queue_size());
+    let engine = reload::build_engine(config, pool.workers())?;
+    let reloader = Reloader::new(config_file.to_path_buf(), pool.workers(), config.admin_key.clone());
+    let app_state = web::Data::new(AppState {
+        engine: RwLock::new(Arc::new(engine)),
+        track: Mutex::new(QueryTracker::new(nowtime)),
+        jobs: jobs.clone(),
+        pool,
+        api_keys: config.api_keys.clone().unwrap_or_default(),
+        reloader,
+    });
+    job_handler::start_worker(app_state.clone(), jobs, job_queue);
+    #[cfg(unix)]
+    actix_web::rt::spawn(reload::reload_on_hangup(app_state.clone()));
 
     HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone())
+            .app_data(web::JsonConfig::default().limit(256 * 1024))
             .service(tts_handler::api_tts)
+            .service(tts_handler::api_tts_post)
+            .service(stream_handler::api_tts_stream)
+            .service(marks_handler::api_tts_marks)
+            .service(mel_handler::api_tts_mel)
+            .service(
+                web::resource("/api/tts/batch")
+                    .app_data(web::JsonConfig::default().limit(batch_handler::MAX_BATCH_BODY_BYTES))
+                    .route(web::post().to(batch_handler::api_tts_batch)),
+            )
+            .service(job_handler::api_job_submit)
+            .service(job_handler::api_job_status)
+            .service(job_handler::api_job_audio)
+            .service(job_handler::api_job_delete)
+            .service(ws_handler::ws_tts)
+            .service(voices_handler::api_voices)
+            .service(admin_handler::api_admin_reload)
             .service(index::index)
             .service(fs::Files::new("/demo", "demo"))
             .service(